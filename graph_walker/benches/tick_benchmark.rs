@@ -28,20 +28,30 @@ fn tick_300_benchmark_2d(c: &mut Criterion) {
 fn tick_300_benchmark_3d(c: &mut Criterion) {
     let mut group = c.benchmark_group("tick algorithm 300 iter");
 
-    let mut universe2D = black_box(Universe2D::new(100, 100000));
-    let mut universe3D = black_box(Universe3D::new(100, 100000));
+    let mut universe_2d = black_box(Universe2D::new(100, 100000));
+    let mut universe_3d = black_box(Universe3D::new(100, 100000));
 
     group.sample_size(10);
-    group.bench_function("2d", |b| b.iter(|| universe2D.iterate(300)));
-    group.bench_function("3d", |b| b.iter(|| universe3D.iterate(300)));
+    group.bench_function("2d", |b| b.iter(|| universe_2d.iterate(300)));
+    group.bench_function("3d", |b| b.iter(|| universe_3d.iterate(300)));
     group.finish();
 }
 
+/**
+ * Graffiti and push strength update alone; compare runs with and without `--features fast_math`
+ */
+fn graffiti_pass_benchmark_2d(c: &mut Criterion) {
+    let mut universe = black_box(Universe2D::new(100, 100000));
+
+    c.bench_function("graffiti pass 2d", |b| b.iter(|| universe.tick_batch(1)));
+}
+
 criterion_group!(
     benches,
     tick_1_benchmark_2d,
     tick_1_benchmark_3d,
     tick_300_benchmark_2d,
-    tick_300_benchmark_3d
+    tick_300_benchmark_3d,
+    graffiti_pass_benchmark_2d
 );
 criterion_main!(benches);
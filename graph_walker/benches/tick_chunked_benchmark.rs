@@ -0,0 +1,22 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use graph_walker::universe::{Universe, Universe2D};
+
+/**
+ * The default rayon partition against contiguous chunks of a few rows
+ */
+fn tick_chunked_benchmark_2d(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick chunked 256x256");
+    let mut universe = black_box(Universe2D::new(256, 500000));
+
+    group.sample_size(20);
+    group.bench_function("rayon default", |b| b.iter(|| universe.tick()));
+    for rows in [1, 4, 16, 64] {
+        group.bench_function(format!("{} rows", rows), |b| {
+            b.iter(|| universe.tick_chunked(rows * 256))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, tick_chunked_benchmark_2d);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+/*!
+ * Build check for the `no_std + alloc` core of `graph_walker`. Build it on its own, so cargo
+ * does not unify its features with the `std` of `graph_walker`'s own targets:
+ *
+ * ```text
+ * cargo build -p no_std_check
+ * ```
+ */
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use graph_walker::universe::{ComputationType, Universe, Universe2D, UniverseState};
+
+/**
+ * Tick a seeded universe serially and in chunks, then pause it, resume it from the snapshot
+ * and tick on. Returns the snapshot and the fingerprints of the resumed universe per tick.
+ */
+pub fn run(size: u32, agents: u32, ticks: u32) -> (UniverseState, Vec<u64>) {
+    let mut universe = Universe2D::new(size, agents);
+    universe.iterate(ticks);
+    universe.tick_with(ComputationType::Serial);
+    universe.tick_with(ComputationType::Chunked(size as usize));
+
+    let state = universe.snapshot();
+    let mut resumed = Universe2D::from_state(state.clone()).expect("a snapshot is a valid state");
+    let fingerprints = (0..ticks)
+        .map(|_| {
+            resumed.tick();
+            resumed.state_fingerprint()
+        })
+        .collect();
+
+    (state, fingerprints)
+}
@@ -1,5 +1,16 @@
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AgentSpecies {
     Red,
     Blue,
 }
+
+/**
+ * Identifies a single agent: the `ordinal`-th agent of `species`, counting through the nodes in
+ * row-major order at the moment tracking starts
+ */
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct AgentId {
+    pub species: AgentSpecies,
+    pub ordinal: u32,
+}
@@ -1,8 +1,20 @@
+use crate::agent_species::AgentSpecies;
+
 #[derive(Clone, Debug, PartialEq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HyperParams {
     pub gamma: f32,
     pub lambda: f32,
     pub beta: f32,
+    pub l0: Option<f32>,          // length scale l of a node, None for l = 1
+    pub persistence: f32,         // extra push to keep moving in the last direction
+    pub red_mass: f32,            // graffiti deposited per red agent, relative to gamma
+    pub blue_mass: f32,           // graffiti deposited per blue agent, relative to gamma
+    pub hop_distance: u32,        // cells moved per tick in the chosen direction, at least 1
+    pub gamma2: f32,              // deposition rate of the pheromone layer
+    pub lambda2: f32,             // decay rate of the pheromone layer
+    pub pheromone_weight: f32,    // share of the pheromone in the push strength, 0 disables it
+    pub interaction: Interaction, // how each species reacts to the graffiti of the other
 }
 
 impl HyperParams {
@@ -11,16 +23,85 @@ impl HyperParams {
             gamma,
             lambda,
             beta,
+            l0: None,
+            persistence: 0.0,
+            red_mass: 1.0,
+            blue_mass: 1.0,
+            hop_distance: 1,
+            gamma2: 0.0,
+            lambda2: 0.0,
+            pheromone_weight: 0.0,
+            interaction: Interaction::Avoid,
+        }
+    }
+
+    /**
+     * A copy of these hyper parameters with `param` set to `value`
+     */
+    pub fn with_param(mut self, param: SensitivityParam, value: f32) -> HyperParams {
+        match param {
+            SensitivityParam::Beta => self.beta = value,
+            SensitivityParam::Gamma => self.gamma = value,
+            SensitivityParam::Lambda => self.lambda = value,
+        }
+        self
+    }
+}
+
+/**
+ * How the agents of each species react to the graffiti of the other species. Attraction flips
+ * the sign in the push strength to exp(beta * graffiti / l^2), so agents are drawn towards
+ * the other species' graffiti.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interaction {
+    #[default]
+    Avoid, // both species move away from the graffiti of the other
+    Attract, // both species move towards the graffiti of the other
+    PredatorPrey {
+        predator: AgentSpecies,
+    }, // the predator chases the prey, the prey flees
+}
+
+impl Interaction {
+    /**
+     * Whether agents of `species` move towards the graffiti of the other species
+     */
+    pub fn attracts(&self, species: AgentSpecies) -> bool {
+        match self {
+            Interaction::Avoid => false,
+            Interaction::Attract => true,
+            Interaction::PredatorPrey { predator } => *predator == species,
         }
     }
 }
 
+/**
+ * A hyper parameter that can be varied in a sensitivity analysis
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Copy)]
+pub enum SensitivityParam {
+    Beta,
+    Gamma,
+    Lambda,
+}
+
 impl Default for HyperParams {
     fn default() -> HyperParams {
         HyperParams {
             gamma: 0.5,
             lambda: 0.5,
             beta: 1.0 / 100.0,
+            l0: None,
+            persistence: 0.0,
+            red_mass: 1.0,
+            blue_mass: 1.0,
+            hop_distance: 1,
+            gamma2: 0.0,
+            lambda2: 0.0,
+            pheromone_weight: 0.0,
+            interaction: Interaction::Avoid,
         }
     }
 }
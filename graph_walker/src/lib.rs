@@ -1,7 +1,25 @@
-mod agent_species;
+/*!
+ * Without the default `std` feature the crate is `no_std + alloc`: `Universe2D::new`, `tick`,
+ * the snapshots (`snapshot`, `into_state`, `from_state`) and the recorders run everywhere, with
+ * every pass serial and the float math from libm. Parallel ticks, background runs, `tick_for`,
+ * `Display`, the analyses, the constructors and the exports need `std`; so do the `arrow`,
+ * `gif` and `spectral` features, which enable it. The `no_std_check` crate builds the core
+ * without `std`.
+ */
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod agent_species;
 pub mod hyper_params;
+mod math;
+pub mod movement_rule;
 mod neighbour_data;
 mod nodes;
+#[cfg(feature = "std")]
+mod noise;
 mod species;
+#[cfg(feature = "std")]
+mod statistics;
 mod testing;
 pub mod universe;
@@ -0,0 +1,92 @@
+/**
+ * e^x for the push strength. The precise `powf` by default; with the `fast_math` feature the
+ * polynomial `fast_exp`, which trades a relative error of up to 1e-5 for speed.
+ */
+#[cfg(not(feature = "fast_math"))]
+pub fn push_exp(x: f32) -> f32 {
+    powf(core::f32::consts::E, x)
+}
+
+#[cfg(feature = "fast_math")]
+pub fn push_exp(x: f32) -> f32 {
+    fast_exp(x)
+}
+
+/**
+ * `base` to the power `exponent`. `core` has no float powers, so without `std` this is libm's.
+ */
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "fast_math", allow(dead_code))]
+pub fn powf(base: f32, exponent: f32) -> f32 {
+    base.powf(exponent)
+}
+
+#[cfg(not(feature = "std"))]
+#[cfg_attr(feature = "fast_math", allow(dead_code))]
+pub fn powf(base: f32, exponent: f32) -> f32 {
+    libm::powf(base, exponent)
+}
+
+/**
+ * `base` to the integer power `n`, libm's `powf` without `std`
+ */
+#[cfg(feature = "std")]
+pub fn powi(base: f32, n: i32) -> f32 {
+    base.powi(n)
+}
+
+#[cfg(not(feature = "std"))]
+pub fn powi(base: f32, n: i32) -> f32 {
+    libm::powf(base, n as f32)
+}
+
+/**
+ * Largest relative error of `fast_exp` against the precise exponential
+ */
+#[cfg(test)]
+pub const FAST_EXP_TOLERANCE: f32 = 1e-5;
+
+/**
+ * Approximate e^x as 2^n * 2^f with x log2(e) = n + f, n an integer and |f| <= 1/2. 2^f comes
+ * from its degree 5 Taylor polynomial and 2^n is written straight into the exponent bits.
+ * Flushes to 0 below about e^-88, where f32 turns subnormal, and saturates at infinity above
+ * about e^88.
+ */
+#[cfg(any(feature = "fast_math", test))]
+pub fn fast_exp(x: f32) -> f32 {
+    // adding and removing 1.5 * 2^23 rounds to the nearest integer without a libm call
+    const ROUND: f32 = 12_582_912.0;
+
+    let t = (x * core::f32::consts::LOG2_E).clamp(-127.0, 128.0);
+    let n = (t + ROUND) - ROUND;
+    let f = (t - n) * core::f32::consts::LN_2;
+    // 2^(t - n) = e^f = 1 + f + f^2/2 + f^3/6 + f^4/24 + f^5/120
+    let fraction =
+        1.0 + f * (1.0 + f * (1.0 / 2.0 + f * (1.0 / 6.0 + f * (1.0 / 24.0 + f * (1.0 / 120.0)))));
+    // at the clamped ends, the biased exponents 0 and 255 make this 0 and infinity
+    let power_of_two = f32::from_bits(((n as i32 + 127) as u32) << 23);
+
+    fraction * power_of_two
+}
+
+#[cfg(test)]
+mod test_math {
+    use super::*;
+
+    #[test]
+    fn test_fast_exp_within_tolerance() {
+        for step in -8000..=800 {
+            let x = step as f32 / 100.0;
+            let (fast, precise) = (fast_exp(x), std::f32::consts::E.powf(x));
+            assert!(
+                ((fast - precise) / precise).abs() < FAST_EXP_TOLERANCE,
+                "e^{}: {} vs {}",
+                x,
+                fast,
+                precise
+            );
+        }
+        assert_eq!(fast_exp(0.0), 1.0);
+        assert_eq!(fast_exp(-200.0), 0.0);
+    }
+}
@@ -0,0 +1,66 @@
+pub use oorandom::Rand32;
+
+use crate::neighbour_data::{NeighbourAgentsOut2D, NeighbourData, NeighbourData2D};
+
+/**
+ * Decides where the agents of a node move to in a tick
+ */
+pub trait MovementRule: Send + Sync {
+    /**
+     * Split `agents` agents over the neighbours (top, right, bottom, left), given the push
+     * strength of the other species at each of them. The returned counts must add up to
+     * `agents`, the universe conserves its agents and only checks this in debug builds.
+     */
+    fn distribute(&self, agents: u32, push: &[f32; 4], prng: &mut Rand32) -> [u32; 4];
+}
+
+/**
+ * The default rule: every agent independently picks a neighbour with a probability
+ * proportional to its push strength
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PushWeighted;
+
+impl MovementRule for PushWeighted {
+    fn distribute(&self, agents: u32, push: &[f32; 4], prng: &mut Rand32) -> [u32; 4] {
+        let total_push: f32 = push.iter().sum();
+        let mut agents_out = NeighbourAgentsOut2D::new(0, 0, 0, 0);
+
+        for _ in 0..agents {
+            agents_out.add_agent_to_random_cell(push, total_push, prng);
+        }
+
+        [
+            agents_out.top,
+            agents_out.right,
+            agents_out.bottom,
+            agents_out.left,
+        ]
+    }
+}
+
+/**
+ * Like `PushWeighted`, with the push strength towards each neighbour (top, right, bottom, left)
+ * scaled by a fixed weight, e.g. to model a drift in one direction
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionWeighted {
+    pub weights: [f32; 4],
+}
+
+impl DirectionWeighted {
+    pub fn new(weights: [f32; 4]) -> DirectionWeighted {
+        DirectionWeighted { weights }
+    }
+}
+
+impl MovementRule for DirectionWeighted {
+    fn distribute(&self, agents: u32, push: &[f32; 4], prng: &mut Rand32) -> [u32; 4] {
+        let mut weighted_push = *push;
+        for (push, weight) in weighted_push.iter_mut().zip(self.weights) {
+            *push *= weight;
+        }
+
+        PushWeighted.distribute(agents, &weighted_push, prng)
+    }
+}
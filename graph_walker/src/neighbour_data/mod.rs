@@ -1,15 +1,21 @@
+#[allow(clippy::module_inception)]
 mod neighbour_data;
 mod neighbour_data_2d;
+#[cfg(feature = "std")]
 mod neighbour_data_3d;
 
 pub use neighbour_data::NeighbourData;
 pub use neighbour_data::NeighbourData2D;
+#[cfg(feature = "std")]
 pub use neighbour_data::NeighbourData3D;
 use neighbour_data_2d::Neighbours2D;
+#[cfg(feature = "std")]
 use neighbour_data_3d::Neighbours3D;
 
 pub type NeigbourIndeces2D = Neighbours2D;
 pub type NeighbourAgentsOut2D = Neighbours2D;
 
+#[cfg(feature = "std")]
 pub type NeigbourIndeces3D = Neighbours3D;
+#[cfg(feature = "std")]
 pub type NeighbourAgentsOut3D = Neighbours3D;
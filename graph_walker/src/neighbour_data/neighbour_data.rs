@@ -1,9 +1,12 @@
 use oorandom::Rand32;
 
 pub trait NeighbourData: IntoIterator {
+    /**
+     * Add one agent to a neighbour drawn with `pick_neighbour`
+     */
     fn add_agent_to_random_cell(
         &mut self,
-        neighbour_push_stengths: &Vec<f32>,
+        neighbour_push_stengths: &[f32],
         total_neighbour_push_stengths: f32,
         prng: &mut Rand32,
     );
@@ -13,6 +16,36 @@ pub trait NeighbourData2D: NeighbourData {
     fn new(top: u32, right: u32, bottom: u32, left: u32) -> Self;
 }
 
+#[cfg(feature = "std")]
 pub trait NeighbourData3D: NeighbourData {
     fn new(top: u32, right: u32, bottom: u32, left: u32, front: u32, back: u32) -> Self;
 }
+
+/**
+ * Draw a neighbour index with a probability proportional to its push strength. Neighbour i
+ * owns the half-open interval [S_(i-1), S_i) of the running sums S of the push strengths, so
+ * a neighbour with zero push strength owns an empty interval and is never drawn, not even
+ * when the random number lands exactly on a boundary such as 0. Rounding can leave the random
+ * number just past the last running sum, then the last neighbour with a positive push
+ * strength is drawn. Without any positive push strength the first neighbour is drawn.
+ */
+pub fn pick_neighbour(
+    neighbour_push_stengths: &[f32],
+    total_neighbour_push_stengths: f32,
+    prng: &mut Rand32,
+) -> usize {
+    let random_number = prng.rand_float() * total_neighbour_push_stengths;
+    let mut sum = 0.0;
+    let mut last_positive = 0;
+    for (i, neighbour_push_stength) in neighbour_push_stengths.iter().enumerate() {
+        if *neighbour_push_stength <= 0.0 {
+            continue;
+        }
+        sum += neighbour_push_stength;
+        if sum > random_number {
+            return i;
+        }
+        last_positive = i;
+    }
+    last_positive
+}
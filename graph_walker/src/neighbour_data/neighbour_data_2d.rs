@@ -1,6 +1,6 @@
 use oorandom::Rand32;
 
-use super::neighbour_data::{NeighbourData, NeighbourData2D};
+use super::neighbour_data::{pick_neighbour, NeighbourData, NeighbourData2D};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Neighbours2D {
@@ -8,30 +8,23 @@ pub struct Neighbours2D {
     pub bottom: u32,
     pub left: u32,
     pub right: u32,
+    #[allow(dead_code)]
     pub size: u32,
 }
 
 impl NeighbourData for Neighbours2D {
     fn add_agent_to_random_cell(
         &mut self,
-        neighbour_push_stengths: &Vec<f32>,
+        neighbour_push_stengths: &[f32],
         total_neighbour_push_stengths: f32,
         prng: &mut Rand32,
     ) {
-        let random_number = prng.rand_float() * total_neighbour_push_stengths;
-        let mut sum = 0.0;
-        for (i, neighbour_push_stength) in neighbour_push_stengths.iter().enumerate() {
-            sum += neighbour_push_stength;
-            if sum >= random_number {
-                match i {
-                    0 => self.top += 1,
-                    1 => self.right += 1,
-                    2 => self.bottom += 1,
-                    3 => self.left += 1,
-                    _ => panic!("Invalid neighbour index"),
-                }
-                break;
-            }
+        match pick_neighbour(neighbour_push_stengths, total_neighbour_push_stengths, prng) {
+            0 => self.top += 1,
+            1 => self.right += 1,
+            2 => self.bottom += 1,
+            3 => self.left += 1,
+            _ => panic!("Invalid neighbour index"),
         }
     }
 }
@@ -127,4 +120,25 @@ mod test_neighbours {
         assert_eq!(neighbours_out.bottom, 30029); // aprox 120_000/4 = 30_000
         assert_eq!(neighbours_out.left, 59847); // aprox 120_000/2 = 60_000
     }
+
+    #[test]
+    fn test_add_agent_to_random_cell_skips_zero_push() {
+        let mut neighbours_out = Neighbours2D::new(0, 0, 0, 0);
+
+        let neighbour_push_stength = vec![0.0, 1.0, 0.0, 0.0];
+        let prng = &mut Rand32::new(0);
+
+        for _ in 0..10_000 {
+            neighbours_out.add_agent_to_random_cell(&neighbour_push_stength, 1.0, prng);
+        }
+        // a total above the sum puts random numbers past the last boundary
+        for _ in 0..10_000 {
+            neighbours_out.add_agent_to_random_cell(&neighbour_push_stength, 2.0, prng);
+        }
+
+        assert_eq!(neighbours_out.top, 0);
+        assert_eq!(neighbours_out.right, 20_000);
+        assert_eq!(neighbours_out.bottom, 0);
+        assert_eq!(neighbours_out.left, 0);
+    }
 }
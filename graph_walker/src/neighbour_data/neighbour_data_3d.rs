@@ -1,6 +1,6 @@
 use oorandom::Rand32;
 
-use super::neighbour_data::{NeighbourData, NeighbourData3D};
+use super::neighbour_data::{pick_neighbour, NeighbourData, NeighbourData3D};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Neighbours3D {
@@ -10,32 +10,25 @@ pub struct Neighbours3D {
     pub right: u32,
     pub front: u32,
     pub back: u32,
+    #[allow(dead_code)]
     pub size: u32,
 }
 
 impl NeighbourData for Neighbours3D {
     fn add_agent_to_random_cell(
         &mut self,
-        neighbour_push_stengths: &Vec<f32>,
+        neighbour_push_stengths: &[f32],
         total_neighbour_push_stengths: f32,
         prng: &mut Rand32,
     ) {
-        let random_number = prng.rand_float() * total_neighbour_push_stengths;
-        let mut sum = 0.0;
-        for (i, neighbour_push_stength) in neighbour_push_stengths.iter().enumerate() {
-            sum += neighbour_push_stength;
-            if sum >= random_number {
-                match i {
-                    0 => self.top += 1,
-                    1 => self.right += 1,
-                    2 => self.bottom += 1,
-                    3 => self.left += 1,
-                    4 => self.front += 1,
-                    5 => self.back += 1,
-                    _ => panic!("Invalid neighbour index"),
-                }
-                break;
-            }
+        match pick_neighbour(neighbour_push_stengths, total_neighbour_push_stengths, prng) {
+            0 => self.top += 1,
+            1 => self.right += 1,
+            2 => self.bottom += 1,
+            3 => self.left += 1,
+            4 => self.front += 1,
+            5 => self.back += 1,
+            _ => panic!("Invalid neighbour index"),
         }
     }
 }
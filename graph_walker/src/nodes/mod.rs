@@ -1,7 +1,9 @@
 mod node;
 mod node_2d;
+#[cfg(feature = "std")]
 mod node_3d;
 
 pub use node::Node;
 pub use node_2d::Node2D;
+#[cfg(feature = "std")]
 pub use node_3d::Node3D;
@@ -1,41 +1,56 @@
 use oorandom::Rand32;
-use std::{collections::HashMap, f32::consts::E};
 
 use crate::{
     agent_species::AgentSpecies,
     hyper_params::HyperParams,
-    neighbour_data::{NeigbourIndeces2D, NeighbourAgentsOut2D, NeighbourData, NeighbourData2D},
+    math::{powi, push_exp},
+    movement_rule::MovementRule,
+    neighbour_data::{NeigbourIndeces2D, NeighbourAgentsOut2D, NeighbourData2D},
     species::{SpeciesGraffiti, SpeciesPushStrength},
 };
 
 use super::Node;
 
+/**
+ * Cap on the exponent of an attracting push strength. e^80 leaves room below the f32 limit
+ * of about e^88.7 for summing four neighbours, scaled by persistence or a movement rule.
+ */
+const MAX_ATTRACTION_EXPONENT: f32 = 80.0;
+
 #[derive(Debug, Clone)]
 pub struct Node2D {
     pub index: u32,
-    pub neighbours: NeigbourIndeces2D,      // indices of neighbours
-    pub graffiti: SpeciesGraffiti,          // {Red_graffiti, Blue_graffiti}
+    pub cell: u32, // grid cell (y * size + x), which also seeds the node's random moves
+    pub neighbours: NeigbourIndeces2D, // indices of neighbours
+    pub graffiti: SpeciesGraffiti, // {Red_graffiti, Blue_graffiti}
+    pub pheromone: SpeciesGraffiti, // second, independently decaying layer of graffiti
     pub push_strength: SpeciesPushStrength, // {Red_graffiti, Blue_graffiti}
     pub blue_agents: u32,
     pub red_agents: u32,
     pub agents_out: [NeighbourAgentsOut2D; 2], // amount of outgoing agents per species
+    pub agents_in: [NeighbourAgentsOut2D; 2],  // incoming agents per species by direction of travel
+    pub active: bool,                          // false if the node is masked out as a wall
 }
 
 impl Node<NeigbourIndeces2D> for Node2D {
-    fn new(index: u32, edges: &HashMap<u32, NeigbourIndeces2D>) -> Node2D {
+    fn new(index: u32, edges: &[NeigbourIndeces2D]) -> Node2D {
         Node2D {
             index,
-            neighbours: edges.get(&index).unwrap().to_owned(),
+            cell: index,
+            neighbours: edges[index as usize],
             graffiti: SpeciesGraffiti::new(0.0, 0.0),
+            pheromone: SpeciesGraffiti::new(0.0, 0.0),
             push_strength: SpeciesPushStrength::new(0.0, 0.0),
             blue_agents: 0,
             red_agents: 0,
             agents_out: [NeighbourAgentsOut2D::new(0, 0, 0, 0); 2],
+            agents_in: [NeighbourAgentsOut2D::new(0, 0, 0, 0); 2],
+            active: true,
         }
     }
 
     fn get_prng(&self) -> Rand32 {
-        Rand32::new((self.index + 1) as u64 * (self.blue_agents + self.red_agents + 1) as u64)
+        Rand32::new((self.cell + 1) as u64 * (self.blue_agents + self.red_agents + 1) as u64)
     }
 
     fn get_push_strength(&self, species: &AgentSpecies) -> f32 {
@@ -54,8 +69,8 @@ impl Node<NeigbourIndeces2D> for Node2D {
 
     fn get_agents_with_species(&self, species: &AgentSpecies) -> u32 {
         match species {
-            AgentSpecies::Blue => self.red_agents,
-            AgentSpecies::Red => self.blue_agents,
+            AgentSpecies::Red => self.red_agents,
+            AgentSpecies::Blue => self.blue_agents,
         }
     }
 
@@ -66,99 +81,274 @@ impl Node<NeigbourIndeces2D> for Node2D {
      * 𝛾 = deposition rate
      * 𝞺_i = sum of graffiti of species i at location x,y multiplied by 1/(l^2) [as defined in paper: 𝞺_i(x, y, t) = n_i(x, y, t)/l2]
      */
-    fn update_graffiti_and_push_strength(&mut self, hyper_params: &HyperParams, _grid_size: u32) {
-        let l_squared: f32 = 1.0; //(1.0 / grid_size as f32).powf(2.0);
-                                  // TODO: check if algorithm still works with grid_size
+    fn update_graffiti_and_push_strength(&mut self, hyper_params: &HyperParams, grid_size: u32) {
+        let l_squared = l_squared(hyper_params, grid_size);
 
         // 0 - Decrement current graffiti by lambda
         self.graffiti.mult_all(1.0 - hyper_params.lambda);
 
-        // 1 - Increase grafiti by gamma * sum of same agent' count
-        self.graffiti
-            .add_red(hyper_params.gamma * self.red_agents as f32 / l_squared);
-        self.graffiti
-            .add_blue(hyper_params.gamma * self.blue_agents as f32 / l_squared);
-
-        // 2 - Calculate push strength
-        self.push_strength
-            .set_red(E.powf(-hyper_params.beta * self.graffiti.red / l_squared));
-        self.push_strength
-            .set_blue(E.powf(-hyper_params.beta * self.graffiti.blue / l_squared));
-    }
+        // 1 - Increase grafiti by gamma * mass * sum of same agent' count
+        self.graffiti.add_red(
+            hyper_params.gamma * hyper_params.red_mass * self.red_agents as f32 / l_squared,
+        );
+        self.graffiti.add_blue(
+            hyper_params.gamma * hyper_params.blue_mass * self.blue_agents as f32 / l_squared,
+        );
 
-    fn move_agents_out(&mut self, nodes: &Vec<Node2D>, _grid_size: u32) {
-        let neighbours_idx = &self.neighbours;
+        // 2 - Same for the pheromone layer, with its own rates
+        self.pheromone.mult_all(1.0 - hyper_params.lambda2);
+        self.pheromone
+            .add_red(hyper_params.gamma2 * self.red_agents as f32 / l_squared);
+        self.pheromone
+            .add_blue(hyper_params.gamma2 * self.blue_agents as f32 / l_squared);
 
-        // 1 - Calculate neighbour strengths
-        let mut total_neigh_push_strengths_red = 0.0;
-        let mut total_neigh_push_strengths_blue = 0.0;
+        // 3 - Calculate push strength
+        self.update_push_strength(hyper_params, grid_size);
+    }
 
-        let neighbour_push_stengths_iter = neighbours_idx.into_iter().map(|neighbour_idx| {
+    fn move_agents_out(
+        &mut self,
+        nodes: &[Node2D],
+        hyper_params: &HyperParams,
+        movement_rule: &dyn MovementRule,
+    ) {
+        // 1 - Collect the push strengths of the neighbours (top, right, bottom, left)
+        let mut red_push_strengths = [0.0; 4];
+        let mut blue_push_strengths = [0.0; 4];
+        for (i, neighbour_idx) in self.neighbours.into_iter().enumerate() {
             let neighbour = &nodes[neighbour_idx as usize];
-            let red_push = neighbour.get_push_strength(&AgentSpecies::Red);
-            let blue_push = neighbour.get_push_strength(&AgentSpecies::Blue);
-
-            total_neigh_push_strengths_red += red_push;
-            total_neigh_push_strengths_blue += blue_push;
-            (red_push, blue_push)
-        });
-
-        // neighbour_push_stengths.0 is a Vec of all red neighbour push strengths
-        // neighbour_push_stengths.1 is a Vec of all blue neighbour push strengths
-        let neighbour_push_stengths: (Vec<f32>, Vec<f32>) = neighbour_push_stengths_iter.unzip(); // Vec<(ps1_red, ps2_blue), (ps_2_red, ps2_blue)> => (Vec(ps1_red, ps_2_red), Vec(ps1_blue, ps2_blue))
-        assert!(neighbour_push_stengths.0.len() == neighbour_push_stengths.1.len());
+            red_push_strengths[i] = neighbour.get_push_strength(&AgentSpecies::Red);
+            blue_push_strengths[i] = neighbour.get_push_strength(&AgentSpecies::Blue);
+        }
 
-        let mut red_agents_out = NeigbourIndeces2D::new(0, 0, 0, 0);
-        let mut blue_agents_out = NeigbourIndeces2D::new(0, 0, 0, 0);
         let mut prng = self.get_prng();
 
-        // 2 - Move agents out
-        for _ in 0..self.red_agents {
-            red_agents_out.add_agent_to_random_cell(
-                &neighbour_push_stengths.1,      // vec of blue push strengths
-                total_neigh_push_strengths_blue, // sum of all blue push strengths
-                &mut prng,
-            );
-        }
+        // 2 - Move agents out, red agents respond to blue push strengths and vice versa
+        let red_agents_out = distribute_with_persistence(
+            self.red_agents,
+            &self.agents_in[0],
+            &blue_push_strengths,
+            hyper_params.persistence,
+            movement_rule,
+            &mut prng,
+        );
+        let blue_agents_out = distribute_with_persistence(
+            self.blue_agents,
+            &self.agents_in[1],
+            &red_push_strengths,
+            hyper_params.persistence,
+            movement_rule,
+            &mut prng,
+        );
 
-        for _ in 0..self.blue_agents {
-            blue_agents_out.add_agent_to_random_cell(
-                &neighbour_push_stengths.0,     // vec of red push strengths
-                total_neigh_push_strengths_red, // sum of all red push strengths
-                &mut prng,
-            );
-        }
+        debug_assert_eq!(
+            red_agents_out.into_iter().sum::<u32>(),
+            self.red_agents,
+            "the movement rule must move every red agent of node {}",
+            self.index
+        );
+        debug_assert_eq!(
+            blue_agents_out.into_iter().sum::<u32>(),
+            self.blue_agents,
+            "the movement rule must move every blue agent of node {}",
+            self.index
+        );
 
         self.agents_out = [red_agents_out, blue_agents_out];
     }
 
-    fn move_agents_in(&mut self, nodes: &Vec<Node2D>) {
+    fn move_agents_in(&mut self, nodes: &[Node2D]) {
         let neighbours_idx = &self.neighbours.clone();
+        let index = self.index;
         self.red_agents = 0;
         self.blue_agents = 0;
 
+        // Agents arriving from a neighbour: the ones it sent towards this node, or, when the
+        // neighbour is this node itself (the edge leads into a wall), the ones that bounced back
+        let arriving = |neighbour_idx: u32,
+                        towards_self: fn(&NeighbourAgentsOut2D) -> u32,
+                        into_wall: fn(&NeighbourAgentsOut2D) -> u32| {
+            let agents_out = nodes[neighbour_idx as usize].agents_out; // [0] is red, [1] is blue
+            let direction = if neighbour_idx == index {
+                into_wall
+            } else {
+                towards_self
+            };
+            (direction(&agents_out[0]), direction(&agents_out[1]))
+        };
+
         // Move agents from the top neighbour to this node which is at the bottom of the top neighbour
-        let top_idx = neighbours_idx.top;
-        let top_node_agents = nodes[top_idx as usize].agents_out;
-        self.add_agents(top_node_agents[0].bottom, AgentSpecies::Red); // top_node_agents[0] is the red agents out of the top neighbour
-        self.add_agents(top_node_agents[1].bottom, AgentSpecies::Blue); // top_node_agents[1] is the blue agents out of the top neighbour
+        let (red_down, blue_down) = arriving(neighbours_idx.top, |out| out.bottom, |out| out.top);
+        self.add_agents(red_down, AgentSpecies::Red);
+        self.add_agents(blue_down, AgentSpecies::Blue);
 
         // Move agents from the right neighbour to this node which is at the left of the right neighbour
-        let right_idx = neighbours_idx.right;
-        let right_node_agents = nodes[right_idx as usize].agents_out;
-        self.add_agents(right_node_agents[0].left, AgentSpecies::Red); // right_node_agents[0] is the red agents out of the right neighbour
-        self.add_agents(right_node_agents[1].left, AgentSpecies::Blue); // right_node_agents[1] is the blue agents out of the right neighbour
+        let (red_left, blue_left) = arriving(neighbours_idx.right, |out| out.left, |out| out.right);
+        self.add_agents(red_left, AgentSpecies::Red);
+        self.add_agents(blue_left, AgentSpecies::Blue);
 
         // Move agents from the bottom neighbour to this node which is at the top of the bottom neighbour
-        let bottom_idx = neighbours_idx.bottom;
-        let bottom_node_agents = nodes[bottom_idx as usize].agents_out;
-        self.add_agents(bottom_node_agents[0].top, AgentSpecies::Red); // bottom_node_agents[0] is the red agents out of the bottom neighbour
-        self.add_agents(bottom_node_agents[1].top, AgentSpecies::Blue); // bottom_node_agents[1] is the blue agents out of the bottom neighbour
+        let (red_up, blue_up) = arriving(neighbours_idx.bottom, |out| out.top, |out| out.bottom);
+        self.add_agents(red_up, AgentSpecies::Red);
+        self.add_agents(blue_up, AgentSpecies::Blue);
 
         // Move agents from the left neighbour to this node which is at the right of the left neighbour
-        let left_idx = neighbours_idx.left;
-        let left_node_agents = nodes[left_idx as usize].agents_out;
-        self.add_agents(left_node_agents[0].right, AgentSpecies::Red); // left_node_agents[0] is the red agents out of the left neighbour
-        self.add_agents(left_node_agents[1].right, AgentSpecies::Blue); // left_node_agents[1] is the blue agents out of the left neighbour
+        let (red_right, blue_right) =
+            arriving(neighbours_idx.left, |out| out.right, |out| out.left);
+        self.add_agents(red_right, AgentSpecies::Red);
+        self.add_agents(blue_right, AgentSpecies::Blue);
+
+        // Agents bouncing off a wall arrive through the edge they left by, so they travel back
+        self.agents_in = [
+            NeighbourAgentsOut2D::new(red_up, red_right, red_down, red_left),
+            NeighbourAgentsOut2D::new(blue_up, blue_right, blue_down, blue_left),
+        ];
+    }
+}
+
+/**
+ * Split `agents` over the neighbours with `movement_rule`. With `persistence` above 0, agents
+ * that arrived travelling in some direction see the push strength of continuing in that
+ * direction scaled by 1 + `persistence`. Falls back to unbiased movement when the arrivals do
+ * not account for all agents, e.g. before the first tick.
+ */
+fn distribute_with_persistence(
+    agents: u32,
+    agents_in: &NeighbourAgentsOut2D,
+    push_strengths: &[f32; 4],
+    persistence: f32,
+    movement_rule: &dyn MovementRule,
+    prng: &mut Rand32,
+) -> NeighbourAgentsOut2D {
+    let arrived = [
+        agents_in.top,
+        agents_in.right,
+        agents_in.bottom,
+        agents_in.left,
+    ];
+    if persistence == 0.0 || arrived.iter().sum::<u32>() != agents {
+        let [top, right, bottom, left] = movement_rule.distribute(agents, push_strengths, prng);
+        return NeighbourAgentsOut2D::new(top, right, bottom, left);
+    }
+
+    let mut agents_out = [0; 4];
+    for (direction, arrived) in arrived.into_iter().enumerate() {
+        let mut biased_push_strengths = *push_strengths;
+        biased_push_strengths[direction] *= 1.0 + persistence;
+
+        let distributed = movement_rule.distribute(arrived, &biased_push_strengths, prng);
+        for (total, agents) in agents_out.iter_mut().zip(distributed) {
+            *total += agents;
+        }
+    }
+
+    let [top, right, bottom, left] = agents_out;
+    NeighbourAgentsOut2D::new(top, right, bottom, left)
+}
+
+/**
+ * Squared length scale l^2 of a node, used to convert agent counts and graffiti into densities.
+ * Taken from `HyperParams::l0` when set, so that the physics does not depend on the resolution.
+ */
+fn l_squared(hyper_params: &HyperParams, _grid_size: u32) -> f32 {
+    match hyper_params.l0 {
+        Some(l0) => l0 * l0,
+        None => 1.0, //(1.0 / grid_size as f32).powf(2.0);
+                     // TODO: check if algorithm still works with grid_size
+    }
+}
+
+/**
+ * Minimal graffiti difference for a species to dominate a node
+ */
+pub const DOMINANCE_THRESHOLD: f32 = 0.1;
+
+impl Node2D {
+    #[cfg(feature = "std")]
+    pub fn get_graffiti(&self, species: &AgentSpecies) -> f32 {
+        match species {
+            AgentSpecies::Red => self.graffiti.red,
+            AgentSpecies::Blue => self.graffiti.blue,
+        }
+    }
+
+    /**
+     * The species whose graffiti dominates this node, `None` if neither exceeds the other by
+     * more than `DOMINANCE_THRESHOLD`
+     */
+    pub fn dominant_species(&self) -> Option<AgentSpecies> {
+        let delta = self.graffiti.blue - self.graffiti.red;
+
+        if delta.abs() < DOMINANCE_THRESHOLD {
+            None
+        } else if delta > 0.0 {
+            Some(AgentSpecies::Blue)
+        } else {
+            Some(AgentSpecies::Red)
+        }
+    }
+
+    /**
+     * Recalculate the push strength of both species from the current graffiti, plus the
+     * pheromone scaled by `HyperParams::pheromone_weight`. The push of one species' graffiti
+     * is felt by the other species, so its sign follows whether that species is attracted.
+     * Attraction saturates at an exponent of `MAX_ATTRACTION_EXPONENT` to stay finite.
+     */
+    pub fn update_push_strength(&mut self, hyper_params: &HyperParams, grid_size: u32) {
+        let l_squared = l_squared(hyper_params, grid_size);
+        let weight = hyper_params.pheromone_weight;
+        let signed = |exponent: f32, felt_by: AgentSpecies| {
+            if hyper_params.interaction.attracts(felt_by) {
+                (-exponent).min(MAX_ATTRACTION_EXPONENT)
+            } else {
+                exponent
+            }
+        };
+
+        self.push_strength.set_red(push_exp(signed(
+            -hyper_params.beta * (self.graffiti.red + weight * self.pheromone.red) / l_squared,
+            AgentSpecies::Blue,
+        )));
+        self.push_strength.set_blue(push_exp(signed(
+            -hyper_params.beta * (self.graffiti.blue + weight * self.pheromone.blue) / l_squared,
+            AgentSpecies::Red,
+        )));
+    }
+
+    /**
+     * Advance the graffiti and the pheromone `n` updates at once, assuming the agent counts
+     * stay fixed. Iterating ξ(t + 1) = (1 - ƛ)ξ(t) + 𝛾𝞺 gives the geometric series
+     * ξ(t + n) = (1 - ƛ)^n ξ(t) + 𝛾𝞺 (1 - (1 - ƛ)^n) / ƛ
+     */
+    pub fn advance_graffiti(&mut self, hyper_params: &HyperParams, grid_size: u32, n: u32) {
+        let l_squared = l_squared(hyper_params, grid_size);
+        let retention = powi(1.0 - hyper_params.lambda, n as i32);
+        let deposition_sum = if hyper_params.lambda == 0.0 {
+            n as f32
+        } else {
+            (1.0 - retention) / hyper_params.lambda
+        };
+
+        self.graffiti.mult_all(retention);
+        self.graffiti.add_red(
+            hyper_params.gamma * hyper_params.red_mass * self.red_agents as f32 / l_squared
+                * deposition_sum,
+        );
+        self.graffiti.add_blue(
+            hyper_params.gamma * hyper_params.blue_mass * self.blue_agents as f32 / l_squared
+                * deposition_sum,
+        );
+
+        let retention = powi(1.0 - hyper_params.lambda2, n as i32);
+        let deposition_sum = if hyper_params.lambda2 == 0.0 {
+            n as f32
+        } else {
+            (1.0 - retention) / hyper_params.lambda2
+        };
+        self.pheromone.mult_all(retention);
+        self.pheromone
+            .add_red(hyper_params.gamma2 * self.red_agents as f32 / l_squared * deposition_sum);
+        self.pheromone
+            .add_blue(hyper_params.gamma2 * self.blue_agents as f32 / l_squared * deposition_sum);
     }
 }
@@ -1,9 +1,9 @@
 use oorandom::Rand32;
-use std::{collections::HashMap, f32::consts::E};
 
 use crate::{
     agent_species::AgentSpecies,
     hyper_params::HyperParams,
+    math::push_exp,
     neighbour_data::{NeigbourIndeces3D, NeighbourAgentsOut3D, NeighbourData, NeighbourData3D},
     species::{SpeciesGraffiti, SpeciesPushStrength},
 };
@@ -20,10 +20,10 @@ pub struct Node3D {
 }
 
 impl Node3D {
-    pub fn new(index: u32, edges: &HashMap<u32, NeigbourIndeces3D>) -> Node3D {
+    pub fn new(index: u32, edges: &[NeigbourIndeces3D]) -> Node3D {
         Node3D {
             index,
-            neighbours: edges.get(&index).unwrap().to_owned(),
+            neighbours: edges[index as usize],
             graffiti: SpeciesGraffiti::new(0.0, 0.0),
             push_strength: SpeciesPushStrength::new(0.0, 0.0),
             blue_agents: 0,
@@ -52,8 +52,8 @@ impl Node3D {
 
     pub fn get_agents_with_species(&self, species: &AgentSpecies) -> u32 {
         match species {
-            AgentSpecies::Blue => self.red_agents,
-            AgentSpecies::Red => self.blue_agents,
+            AgentSpecies::Red => self.red_agents,
+            AgentSpecies::Blue => self.blue_agents,
         }
     }
 
@@ -75,20 +75,23 @@ impl Node3D {
         // 0 - Decrement current graffiti by lambda
         self.graffiti.mult_all(1.0 - hyper_params.lambda);
 
-        // 1 - Increase grafiti by gamma * sum of same agent' count
-        self.graffiti
-            .add_red(hyper_params.gamma * self.red_agents as f32 / l_squared);
-        self.graffiti
-            .add_blue(hyper_params.gamma * self.blue_agents as f32 / l_squared);
+        // 1 - Increase grafiti by gamma * mass * sum of same agent' count
+        self.graffiti.add_red(
+            hyper_params.gamma * hyper_params.red_mass * self.red_agents as f32 / l_squared,
+        );
+        self.graffiti.add_blue(
+            hyper_params.gamma * hyper_params.blue_mass * self.blue_agents as f32 / l_squared,
+        );
 
         // 2 - Calculate push strength
         self.push_strength
-            .set_red(E.powf(-hyper_params.beta * self.graffiti.red / l_squared));
-        self.push_strength
-            .set_blue(E.powf(-hyper_params.beta * self.graffiti.blue / l_squared));
+            .set_red(push_exp(-hyper_params.beta * self.graffiti.red / l_squared));
+        self.push_strength.set_blue(push_exp(
+            -hyper_params.beta * self.graffiti.blue / l_squared,
+        ));
     }
 
-    pub fn move_agents_out(&mut self, nodes: &Vec<Node3D>, _grid_size: u32) {
+    pub fn move_agents_out(&mut self, nodes: &[Node3D], _grid_size: u32) {
         let neighbours_idx = &self.neighbours;
 
         // 1 - Calculate neighbour strengths
@@ -134,7 +137,7 @@ impl Node3D {
         self.agents_out = [red_agents_out, blue_agents_out];
     }
 
-    pub fn move_agents_in(&mut self, nodes: &Vec<Node3D>) {
+    pub fn move_agents_in(&mut self, nodes: &[Node3D]) {
         let neighbours_idx = &self.neighbours.clone();
         self.red_agents = 0;
         self.blue_agents = 0;
@@ -0,0 +1,111 @@
+use oorandom::Rand32;
+
+/**
+ * Ken Perlin's improved gradient noise in two dimensions, driven by a permutation table
+ * shuffled from a seed
+ */
+pub struct Perlin {
+    permutation: [u8; 512], // a permutation of 0..=255, repeated so lookups need no wrapping
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut prng = Rand32::new(seed);
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        for i in (1..256).rev() {
+            let j = prng.rand_range(0..i as u32 + 1) as usize;
+            table.swap(i, j);
+        }
+
+        Perlin {
+            permutation: std::array::from_fn(|i| table[i % 256]),
+        }
+    }
+
+    /**
+     * Noise at (x, y), in about [-1, 1]. Smooth in between the integer lattice points, where it
+     * is 0, and repeating every 256 units.
+     */
+    pub fn noise(&self, x: f32, y: f32) -> f32 {
+        let (x_floor, y_floor) = (x.floor(), y.floor());
+        let (xi, yi) = (
+            (x_floor as i32 & 255) as usize,
+            (y_floor as i32 & 255) as usize,
+        );
+        let (xf, yf) = (x - x_floor, y - y_floor);
+        let (u, v) = (fade(xf), fade(yf));
+
+        let p = &self.permutation;
+        let hash = |dx: usize, dy: usize| p[p[xi + dx] as usize + yi + dy];
+
+        let bottom = lerp(
+            u,
+            gradient(hash(0, 0), xf, yf),
+            gradient(hash(1, 0), xf - 1.0, yf),
+        );
+        let top = lerp(
+            u,
+            gradient(hash(0, 1), xf, yf - 1.0),
+            gradient(hash(1, 1), xf - 1.0, yf - 1.0),
+        );
+        lerp(v, bottom, top)
+    }
+}
+
+/**
+ * The smoothstep 6t^5 - 15t^4 + 10t^3, which has zero first and second derivatives at 0 and 1
+ */
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/**
+ * Dot product of (x, y) with one of eight gradient directions picked by `hash`
+ */
+fn gradient(hash: u8, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+#[cfg(test)]
+mod test_noise {
+    use super::*;
+
+    #[test]
+    fn test_perlin_noise() {
+        let perlin = Perlin::new(7);
+        assert_eq!(perlin.noise(3.0, 5.0), 0.0);
+
+        let mut values = Vec::new();
+        for step in 0..2000 {
+            let (x, y) = (step as f32 * 0.137, step as f32 * 0.071);
+            let value = perlin.noise(x, y);
+            assert!(
+                (-1.0..=1.0).contains(&value),
+                "noise({}, {}) = {}",
+                x,
+                y,
+                value
+            );
+            // continuous: a small step changes the value only a little
+            assert!((perlin.noise(x + 0.01, y) - value).abs() < 0.05);
+            values.push(value);
+        }
+        assert!(values.iter().any(|value| value.abs() > 0.2));
+
+        assert_eq!(Perlin::new(7).noise(1.3, 2.6), perlin.noise(1.3, 2.6));
+        assert_ne!(Perlin::new(8).noise(1.3, 2.6), perlin.noise(1.3, 2.6));
+    }
+}
@@ -1,4 +1,4 @@
-use std::ops::{AddAssign, MulAssign};
+use core::ops::{AddAssign, MulAssign};
 
 pub type SpeciesGraffiti = Species<f32>;
 pub type SpeciesPushStrength = Species<f32>;
@@ -0,0 +1,163 @@
+/**
+ * Welford's online algorithm for the running mean and variance of a series
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Welford {
+    count: u32,
+    mean: f64,
+    m2: f64,
+}
+
+impl Welford {
+    pub fn new() -> Welford {
+        Welford::default()
+    }
+
+    pub fn push(&mut self, value: f32) {
+        self.count += 1;
+        let delta = value as f64 - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value as f64 - self.mean);
+    }
+
+    pub fn mean(&self) -> f32 {
+        self.mean as f32
+    }
+
+    /**
+     * Sample variance, 0 for fewer than two values
+     */
+    pub fn variance(&self) -> f32 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.count - 1) as f64) as f32
+    }
+
+    pub fn std(&self) -> f32 {
+        self.variance().sqrt()
+    }
+}
+
+/**
+ * Welford's online algorithm extended to the running covariance of a series of pairs
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WelfordCovariance {
+    count: u32,
+    mean_x: f64,
+    mean_y: f64,
+    comoment: f64,
+}
+
+impl WelfordCovariance {
+    pub fn new() -> WelfordCovariance {
+        WelfordCovariance::default()
+    }
+
+    pub fn push(&mut self, x: f32, y: f32) {
+        self.count += 1;
+        let delta_x = x as f64 - self.mean_x;
+        self.mean_x += delta_x / self.count as f64;
+        self.mean_y += (y as f64 - self.mean_y) / self.count as f64;
+        self.comoment += delta_x * (y as f64 - self.mean_y);
+    }
+
+    /**
+     * Sample covariance, 0 for fewer than two pairs
+     */
+    pub fn covariance(&self) -> f32 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.comoment / (self.count - 1) as f64) as f32
+    }
+}
+
+/**
+ * The `q` quantile (0 to 1) of ascending `sorted` values, interpolating linearly between the
+ * two closest ranks. NaN for no values.
+ */
+pub fn quantile(sorted: &[f32], q: f32) -> f32 {
+    if sorted.is_empty() {
+        return f32::NAN;
+    }
+
+    let rank = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f32;
+    let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[below] + (sorted[above] - sorted[below]) * (rank - below as f32)
+}
+
+/**
+ * Weighted circular mean of positions on a ring of length `period`, in [0, `period`).
+ * `None` if the weights sum to zero.
+ */
+pub fn circular_mean(values: impl Iterator<Item = (f32, f32)>, period: f32) -> Option<f32> {
+    let (mut cos_sum, mut sin_sum, mut weight_sum) = (0.0f64, 0.0f64, 0.0f64);
+    for (position, weight) in values {
+        let angle = std::f64::consts::TAU * position as f64 / period as f64;
+        cos_sum += weight as f64 * angle.cos();
+        sin_sum += weight as f64 * angle.sin();
+        weight_sum += weight as f64;
+    }
+
+    if weight_sum == 0.0 {
+        return None;
+    }
+    let angle = sin_sum.atan2(cos_sum).rem_euclid(std::f64::consts::TAU);
+    Some(((angle / std::f64::consts::TAU * period as f64) as f32) % period)
+}
+
+#[cfg(test)]
+mod test_statistics {
+    use super::*;
+
+    #[test]
+    fn test_welford() {
+        let mut welford = Welford::new();
+        assert_eq!(welford.variance(), 0.0);
+
+        [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]
+            .iter()
+            .for_each(|value| welford.push(*value));
+
+        assert_eq!(welford.mean(), 5.0);
+        assert!((welford.variance() - 32.0 / 7.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_welford_covariance() {
+        let mut welford = WelfordCovariance::new();
+        assert_eq!(welford.covariance(), 0.0);
+
+        [(1.0, 2.0), (2.0, 4.0), (3.0, 5.0), (4.0, 4.0), (5.0, 5.0)]
+            .iter()
+            .for_each(|(x, y)| welford.push(*x, *y));
+
+        // means 3 and 4, Σ(x - 3)(y - 4) = 6
+        assert!((welford.covariance() - 6.0 / 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_quantile() {
+        assert!(quantile(&[], 0.5).is_nan());
+        assert_eq!(quantile(&[3.0], 0.9), 3.0);
+
+        let sorted = [1.0, 2.0, 4.0, 8.0, 16.0];
+        assert_eq!(quantile(&sorted, 0.0), 1.0);
+        assert_eq!(quantile(&sorted, 0.5), 4.0);
+        assert_eq!(quantile(&sorted, 0.625), 6.0);
+        assert_eq!(quantile(&sorted, 1.0), 16.0);
+    }
+
+    #[test]
+    fn test_circular_mean_wraps_around() {
+        assert_eq!(circular_mean([(1.0, 0.0)].into_iter(), 10.0), None);
+
+        let mean = circular_mean([(9.0, 1.0), (1.0, 1.0)].into_iter(), 10.0).unwrap();
+        assert!(!(1e-4..=10.0 - 1e-4).contains(&mean), "mean {}", mean);
+
+        let mean = circular_mean([(2.0, 3.0), (4.0, 1.0)].into_iter(), 100.0).unwrap();
+        assert!((mean - 2.5).abs() < 0.01, "mean {}", mean);
+    }
+}
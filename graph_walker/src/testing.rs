@@ -1,23 +1,18 @@
 #[cfg(test)]
 mod test_1 {
-    use std::{
-        collections::HashMap,
-        sync::{Arc, Mutex},
-    };
+    use std::sync::{Arc, Mutex};
 
     use rand::Rng;
     use rayon::prelude::*;
 
     use crate::{
         agent_species::AgentSpecies,
-        neighbour_data::{NeigbourIndeces2D, NeighbourData2D},
-        nodes::{Node, Node2D},
+        neighbour_data::{NeigbourIndeces2D, NeigbourIndeces3D, NeighbourData2D, NeighbourData3D},
+        nodes::{Node, Node2D, Node3D},
     };
 
     fn default_node() -> Node2D {
-        let mut edges = HashMap::new();
-        edges.insert(0, NeigbourIndeces2D::new(1, 2, 3, 4));
-        let node = Node2D::new(0, &edges);
+        let node = Node2D::new(0, &[NeigbourIndeces2D::new(1, 2, 3, 4)]);
 
         assert_eq!(node.blue_agents, 0);
         assert_eq!(node.red_agents, 0);
@@ -73,4 +68,21 @@ mod test_1 {
 
         assert_eq!(total_blue_agents as usize, SIZE);
     }
+
+    #[test]
+    fn it_counts_the_agents_of_each_species() {
+        let mut node = default_node();
+        node.add_agents(3, AgentSpecies::Red);
+        node.add_agents(5, AgentSpecies::Blue);
+
+        assert_eq!(node.get_agents_with_species(&AgentSpecies::Red), 3);
+        assert_eq!(node.get_agents_with_species(&AgentSpecies::Blue), 5);
+
+        let mut node = Node3D::new(0, &[NeigbourIndeces3D::new(1, 2, 3, 4, 5, 6)]);
+        node.add_agents(3, AgentSpecies::Red);
+        node.add_agents(5, AgentSpecies::Blue);
+
+        assert_eq!(node.get_agents_with_species(&AgentSpecies::Red), 3);
+        assert_eq!(node.get_agents_with_species(&AgentSpecies::Blue), 5);
+    }
 }
@@ -0,0 +1,13 @@
+use crate::agent_species::AgentSpecies;
+
+/**
+ * Agents of one species that moved from one grid cell to another in a tick, by cell
+ * (y * size + x)
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AgentFlow {
+    pub from_cell: u32,
+    pub to_cell: u32,
+    pub species: AgentSpecies,
+    pub count: u32,
+}
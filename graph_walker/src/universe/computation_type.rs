@@ -0,0 +1,13 @@
+/**
+ * Whether the passes of a tick run over the nodes serially or in parallel
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputationType {
+    Serial,
+    Parallel,
+    /**
+     * In parallel over contiguous blocks of this many nodes, each handled serially by one
+     * thread. Sized to fit a cache, neighbouring rows stay together.
+     */
+    Chunked(usize),
+}
@@ -0,0 +1,23 @@
+/**
+ * What the `Display` implementation of a universe draws for every node
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayMode {
+    /**
+     * The species whose graffiti dominates the node
+     */
+    #[default]
+    GraffitiDominance,
+    /**
+     * The number of agents of both species, shaded relative to the fullest node
+     */
+    AgentCount,
+    /**
+     * The red push strength ratio, red / (red + blue), from blue (0) to red (1)
+     */
+    PushStrength,
+    /**
+     * The graffiti of both species, shaded relative to the most marked node
+     */
+    GraffitiValue,
+}
@@ -0,0 +1,48 @@
+use core::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UniverseError {
+    SizeMismatch { expected: u32, found: u32 },
+    LengthMismatch { expected: usize, found: usize },
+    InvalidCell { node: usize, cell: u32 },
+    NeighbourOutOfRange { node: usize, neighbour: u32 },
+    NonFiniteGraffiti { node: usize },
+    NonFiniteHyperParams,
+    ZeroHopDistance,
+    InvalidTemperature { temperature: f32 },
+    NodeOutOfRange { node: u32 },
+    ActiveMismatch { cell: u32 },
+}
+
+impl fmt::Display for UniverseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UniverseError::SizeMismatch { expected, found } => {
+                write!(f, "size mismatch: expected {}, found {}", expected, found)
+            }
+            UniverseError::LengthMismatch { expected, found } => {
+                write!(f, "expected {} values, found {}", expected, found)
+            }
+            UniverseError::InvalidCell { node, cell } => {
+                write!(f, "node {} has an invalid or duplicate cell {}", node, cell)
+            }
+            UniverseError::NeighbourOutOfRange { node, neighbour } => {
+                write!(f, "node {} has out of range neighbour {}", node, neighbour)
+            }
+            UniverseError::NonFiniteGraffiti { node } => {
+                write!(f, "node {} has non-finite graffiti", node)
+            }
+            UniverseError::NonFiniteHyperParams => write!(f, "hyper parameters are not finite"),
+            UniverseError::ZeroHopDistance => write!(f, "hop distance must be at least 1"),
+            UniverseError::InvalidTemperature { temperature } => {
+                write!(f, "temperature must be above 0, found {}", temperature)
+            }
+            UniverseError::NodeOutOfRange { node } => write!(f, "node {} does not exist", node),
+            UniverseError::ActiveMismatch { cell } => {
+                write!(f, "cell {} is a wall in only one of the universes", cell)
+            }
+        }
+    }
+}
+
+impl Error for UniverseError {}
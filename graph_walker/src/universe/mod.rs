@@ -1,7 +1,29 @@
+mod agent_flow;
+mod computation_type;
+mod display_mode;
+mod error;
+#[cfg(feature = "std")]
+mod simulation_builder;
+#[cfg(feature = "std")]
+mod simulation_group;
+mod tick_stats;
+#[allow(clippy::module_inception)]
 mod universe;
 mod universe_2d;
+#[cfg(feature = "std")]
 pub mod universe_3d;
+mod universe_state;
 
+pub use agent_flow::AgentFlow;
+pub use computation_type::ComputationType;
+pub use display_mode::DisplayMode;
+pub use error::UniverseError;
+#[cfg(feature = "std")]
+pub use simulation_builder::{Grid2D, Grid3D, SimulationBuilder, Topology};
+#[cfg(feature = "std")]
+pub use simulation_group::SimulationGroup;
+pub use tick_stats::{EnsembleStats, MeasurementResult, TickStats};
 pub use universe::Universe;
 pub use universe_2d::Universe2D;
+pub use universe_state::{CompressedDelta, NodeState, UniverseState};
 // pub use universe_3d::Universe3D;
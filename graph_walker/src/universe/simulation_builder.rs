@@ -0,0 +1,220 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use super::{universe::Universe, universe_3d::Universe3D, Universe2D};
+use crate::{hyper_params::HyperParams, movement_rule::MovementRule};
+
+/**
+ * The shape of the universe a `SimulationBuilder` builds
+ */
+pub trait Topology: Sized {
+    type Universe: Universe + 'static;
+
+    /**
+     * Build the universe of this topology from the options of `builder`
+     */
+    fn build(builder: SimulationBuilder<Self>) -> Self::Universe;
+}
+
+/**
+ * A toroidal 2D grid, built as a `Universe2D`
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Grid2D;
+
+impl Topology for Grid2D {
+    type Universe = Universe2D;
+
+    fn build(builder: SimulationBuilder<Grid2D>) -> Universe2D {
+        let mut universe = Universe2D::new_seeded(builder.size, builder.agent_size, builder.seed);
+        universe.set_hyper_params(builder.hyper_params);
+        if let Some(movement_rule) = builder.movement_rule {
+            universe.set_shared_movement_rule(movement_rule);
+        }
+        universe
+    }
+}
+
+/**
+ * A toroidal 3D grid, built as a `Universe3D`
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Grid3D;
+
+impl Topology for Grid3D {
+    type Universe = Universe3D;
+
+    fn build(builder: SimulationBuilder<Grid3D>) -> Universe3D {
+        let mut universe = Universe3D::new_seeded(builder.size, builder.agent_size, builder.seed);
+        universe.set_hyper_params(builder.hyper_params);
+        universe
+    }
+}
+
+/**
+ * Fluent construction of any universe. Unset options keep their defaults: a size of 100, no
+ * agents, the seed 100 of `Universe::new`, `HyperParams::default()` and the default movement
+ * rule. Both grids wrap around their edges and connect every node to its nearest neighbours
+ * along each axis; neither can be configured.
+ *
+ * # Examples
+ * ```
+ * use graph_walker::hyper_params::HyperParams;
+ * use graph_walker::movement_rule::DirectionWeighted;
+ * use graph_walker::universe::{Grid2D, Grid3D, SimulationBuilder, Universe};
+ *
+ * let mut universe: Box<dyn Universe> = SimulationBuilder::<Grid2D>::new()
+ *     .size(16)
+ *     .agent_size(200)
+ *     .seed(42)
+ *     .hyper_params(HyperParams::new(0.5, 0.1, 0.2))
+ *     .movement_rule(DirectionWeighted::new([1.0, 2.0, 1.0, 1.0]))
+ *     .build();
+ * universe.iterate(10);
+ *
+ * // the same options build a 3D universe
+ * let mut cube = SimulationBuilder::<Grid3D>::new().size(4).agent_size(20).build();
+ * cube.tick();
+ *
+ * // `build_universe` keeps the concrete type and its full API
+ * let grid = SimulationBuilder::<Grid2D>::new().size(8).agent_size(10).build_universe();
+ * assert_eq!(grid.tick_stats().red_agents, 10);
+ * ```
+ */
+#[derive(Clone)]
+pub struct SimulationBuilder<Topo: Topology> {
+    size: u32,
+    agent_size: u32,
+    seed: u64,
+    hyper_params: HyperParams,
+    movement_rule: Option<Arc<dyn MovementRule>>,
+    topology: PhantomData<Topo>,
+}
+
+impl<Topo: Topology> SimulationBuilder<Topo> {
+    pub fn new() -> SimulationBuilder<Topo> {
+        SimulationBuilder {
+            size: 100,
+            agent_size: 0,
+            seed: 100,
+            hyper_params: HyperParams::default(),
+            movement_rule: None,
+            topology: PhantomData,
+        }
+    }
+
+    /**
+     * Number of nodes along each axis
+     */
+    pub fn size(mut self, size: u32) -> Self {
+        self.size = size;
+        self
+    }
+
+    /**
+     * Number of agents of each species, spread over random nodes
+     */
+    pub fn agent_size(mut self, agent_size: u32) -> Self {
+        self.agent_size = agent_size;
+        self
+    }
+
+    /**
+     * Seed of the PRNG that places the agents, see `Universe::new_seeded`
+     */
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn hyper_params(mut self, hyper_params: HyperParams) -> Self {
+        self.hyper_params = hyper_params;
+        self
+    }
+
+    /**
+     * The configured universe behind the `Universe` trait
+     */
+    pub fn build(self) -> Box<dyn Universe> {
+        Box::new(self.build_universe())
+    }
+
+    /**
+     * The configured universe as its concrete type, e.g. `Universe2D` for `Grid2D`
+     */
+    pub fn build_universe(self) -> Topo::Universe {
+        Topo::build(self)
+    }
+}
+
+impl SimulationBuilder<Grid2D> {
+    /**
+     * Rule deciding where agents move, see `Universe2D::set_movement_rule`
+     */
+    pub fn movement_rule<R: MovementRule + 'static>(mut self, movement_rule: R) -> Self {
+        self.movement_rule = Some(Arc::new(movement_rule));
+        self
+    }
+}
+
+impl<Topo: Topology> Default for SimulationBuilder<Topo> {
+    fn default() -> Self {
+        SimulationBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod test_simulation_builder {
+    use super::*;
+    use crate::movement_rule::DirectionWeighted;
+
+    #[test]
+    fn test_builder_matches_universe_new() {
+        let hyper_params = HyperParams::new(0.5, 0.1, 0.2);
+        let built = SimulationBuilder::<Grid2D>::new()
+            .size(8)
+            .agent_size(50)
+            .hyper_params(hyper_params)
+            .build_universe();
+
+        let mut expected = Universe2D::new(8, 50);
+        expected.set_hyper_params(hyper_params);
+        assert_eq!(built.snapshot(), expected.snapshot());
+
+        let reseeded = SimulationBuilder::<Grid2D>::new()
+            .size(8)
+            .agent_size(50)
+            .seed(7)
+            .build_universe();
+        assert_ne!(reseeded.snapshot().nodes, expected.snapshot().nodes);
+        assert_eq!(
+            reseeded.snapshot(),
+            Universe2D::new_seeded(8, 50, 7).snapshot()
+        );
+    }
+
+    #[test]
+    fn test_builder_sets_movement_rule() {
+        let rule = DirectionWeighted::new([1.0, 4.0, 1.0, 1.0]);
+        let mut built = SimulationBuilder::<Grid2D>::new()
+            .size(8)
+            .agent_size(300)
+            .movement_rule(rule)
+            .build_universe();
+        let mut expected = Universe2D::new(8, 300);
+        expected.set_movement_rule(rule);
+
+        built.iterate(3);
+        expected.iterate(3);
+        assert_eq!(built.snapshot(), expected.snapshot());
+    }
+
+    #[test]
+    fn test_builder_builds_3d() {
+        let mut universe = SimulationBuilder::<Grid3D>::new()
+            .size(3)
+            .agent_size(10)
+            .build();
+        universe.iterate(2);
+        assert!(format!("{}", universe).contains("iterations: 2"));
+    }
+}
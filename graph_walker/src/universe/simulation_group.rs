@@ -0,0 +1,73 @@
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+use super::{ComputationType, Universe2D};
+
+/**
+ * An ensemble of universes ticked together on a thread pool of their own. Parallelism is
+ * across universes, each universe runs its passes serially, which avoids nested parallelism.
+ */
+pub struct SimulationGroup {
+    pool: ThreadPool,
+    universes: Vec<Universe2D>,
+}
+
+impl SimulationGroup {
+    /**
+     * Create a group running on `num_threads` threads, 0 picks the rayon default
+     */
+    pub fn new(
+        universes: Vec<Universe2D>,
+        num_threads: usize,
+    ) -> Result<SimulationGroup, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new().num_threads(num_threads).build()?;
+
+        Ok(SimulationGroup { pool, universes })
+    }
+
+    /**
+     * Tick every universe once
+     */
+    pub fn tick_all(&mut self) {
+        let universes = &mut self.universes;
+        self.pool.install(|| {
+            universes
+                .par_iter_mut()
+                .for_each(|universe| universe.tick_with(ComputationType::Serial));
+        });
+    }
+
+    pub fn universes(&self) -> &[Universe2D] {
+        &self.universes
+    }
+
+    pub fn universes_mut(&mut self) -> &mut [Universe2D] {
+        &mut self.universes
+    }
+
+    pub fn into_universes(self) -> Vec<Universe2D> {
+        self.universes
+    }
+}
+
+#[cfg(test)]
+mod test_simulation_group {
+    use super::*;
+    use crate::universe::Universe;
+
+    #[test]
+    fn test_tick_all() {
+        let mut later = Universe2D::new(4, 20);
+        later.iterate(3);
+        let universes = vec![Universe2D::new(4, 20), Universe2D::new(5, 40), later];
+        let mut group = SimulationGroup::new(universes, 2).unwrap();
+
+        group.tick_all();
+
+        let iterations: Vec<u32> = group
+            .universes()
+            .iter()
+            .map(|universe| universe.tick_stats().iteration)
+            .collect();
+        assert_eq!(iterations, vec![1, 1, 4]);
+    }
+}
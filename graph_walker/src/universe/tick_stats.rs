@@ -0,0 +1,43 @@
+/**
+ * Summary statistics of a universe, collected after a tick
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickStats {
+    pub iteration: u32,
+    pub red_agents: u32,
+    pub blue_agents: u32,
+    pub red_graffiti: f32,
+    pub blue_graffiti: f32,
+    pub segregation_index: f32,
+    pub energy: f32,
+    pub species_boundary_length: u32,
+    pub flow_entropy: f32,
+}
+
+/**
+ * Mean and standard deviation of the stats accumulated over a measurement run
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeasurementResult {
+    pub samples: u32,
+    pub mean_segregation_index: f32,
+    pub std_segregation_index: f32,
+    pub mean_energy: f32,
+    pub std_energy: f32,
+}
+
+/**
+ * Distribution of the final segregation index over an ensemble of runs from different seeds.
+ * The percentiles interpolate linearly between the sorted runs.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnsembleStats {
+    pub runs: u32,
+    pub mean_segregation: f32,
+    pub std_segregation: f32,
+    pub min_segregation: f32,
+    pub max_segregation: f32,
+    pub p10: f32,
+    pub p50: f32,
+    pub p90: f32,
+}
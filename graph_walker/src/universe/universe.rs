@@ -1,9 +1,36 @@
-use std::fmt::{Debug, Display};
+use core::fmt::Debug;
+#[cfg(feature = "std")]
+use core::fmt::Display;
 
 use crate::hyper_params::HyperParams;
 
-pub trait Universe: Debug + Display {
-    fn new(size: u32, agent_size: u32) -> Self;
+/**
+ * `Display` with the `std` feature, which draws the universes; nothing without it
+ */
+#[cfg(feature = "std")]
+pub trait Draw: Display {}
+#[cfg(feature = "std")]
+impl<T: Display> Draw for T {}
+
+#[cfg(not(feature = "std"))]
+pub trait Draw {}
+#[cfg(not(feature = "std"))]
+impl<T> Draw for T {}
+
+pub trait Universe: Debug + Draw {
+    /**
+     * Create a universe with `agent_size` agents of each species at random nodes, placed by a
+     * PRNG seeded with `seed`
+     */
+    fn new_seeded(size: u32, agent_size: u32, seed: u64) -> Self
+    where
+        Self: Sized;
+    fn new(size: u32, agent_size: u32) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new_seeded(size, agent_size, 100)
+    }
     fn set_hyper_params(&mut self, hyper_params: HyperParams);
     fn tick(&mut self);
     fn iterate(&mut self, iterations: u32);
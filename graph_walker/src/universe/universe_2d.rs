@@ -1,33 +1,132 @@
-use super::universe::Universe;
+#[cfg(feature = "std")]
+use super::TickStats;
+use super::{universe::Universe, ComputationType, DisplayMode, UniverseError};
 use crate::{
-    agent_species::AgentSpecies,
+    agent_species::{AgentId, AgentSpecies},
     hyper_params::HyperParams,
+    movement_rule::{MovementRule, PushWeighted},
     neighbour_data::NeigbourIndeces2D,
     neighbour_data::NeighbourData2D,
     nodes::{Node, Node2D},
 };
+use alloc::{collections::BTreeMap, string::ToString, sync::Arc, vec, vec::Vec};
+use core::fmt;
 use oorandom::Rand32;
-use pad::PadStr;
+#[cfg(feature = "std")]
 use rayon::prelude::*;
-use std::{collections::HashMap, fmt};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
 
+use deltas::DeltaRecording;
+use injections::Injection;
+use occupancy::OccupancyWindow;
+use population::BirthDeath;
+use schedule::HyperParamsSchedule;
+
+#[cfg(feature = "std")]
+mod analysis;
+#[cfg(feature = "gif")]
+mod animation;
+#[cfg(feature = "std")]
+mod background;
+#[cfg(feature = "std")]
+mod constructors;
+mod deltas;
+#[cfg(feature = "std")]
+mod export;
+#[cfg(feature = "std")]
+mod graph_cut;
+mod injections;
+#[cfg(feature = "std")]
+mod mixing;
+#[cfg(feature = "std")]
+mod noise_field;
+mod occupancy;
+mod population;
+#[cfg(feature = "arrow")]
+mod record_batch;
+mod residence;
+mod schedule;
+#[cfg(feature = "spectral")]
+mod spectral;
+mod state;
+#[cfg(test)]
+pub(crate) mod test_support;
+mod topology;
+mod tracking;
+mod visits;
+
+/**
+ * Largest graffiti change per update at which `relax_graffiti` considers the field settled
+ */
+const RELAX_TOLERANCE: f32 = 1e-6;
+
+#[derive(Clone)]
 pub struct Universe2D {
     size: u32,
     nodes: Vec<Node2D>,
     iteration: u32,
     hyper_params: HyperParams,
+    // node of each grid cell (u32::MAX once removed), None while node i is on cell i
+    cell_nodes: Option<Vec<u32>>,
+    movement_rule: Arc<dyn MovementRule>,
+    occupancy: Option<OccupancyWindow>,
+    trajectories: BTreeMap<AgentId, Vec<(u32, u32)>>, // positions of the tracked agents
+    visit_counts: Option<[Vec<u64>; 2]>,              // agent-ticks per cell, [red, blue]
+    residence_counts: Option<Vec<(u64, u64)>>,        // (agent-ticks, departures) per node
+    delta_recording: Option<DeltaRecording>,
+    injections: Vec<Injection>,      // periodic agent sources
+    birth_death: Option<BirthDeath>, // None conserves the agents
+    display_mode: DisplayMode,
+    temperature: f32, // divides beta in the push strength, 1 leaves the model unchanged
+    schedule: Option<HyperParamsSchedule>,
 }
 
 impl Universe for Universe2D {
-    fn new(size: u32, agent_size: u32) -> Universe2D {
-        let mut prng = Rand32::new(100);
+    fn new_seeded(size: u32, agent_size: u32, seed: u64) -> Universe2D {
+        let mut prng = Rand32::new(seed);
+        let mut universe = Universe2D::empty(size);
+
+        // Set initial agents
+        (0..agent_size * 2).for_each(|id| {
+            let node_index = prng.rand_range(0..(size * size));
+            let species = if id % 2 == 0 {
+                AgentSpecies::Red
+            } else {
+                AgentSpecies::Blue
+            };
+
+            universe.nodes[node_index as usize].add_agents(1, species);
+        });
+
+        universe
+    }
+
+    fn set_hyper_params(&mut self, hyper_params: HyperParams) {
+        self.hyper_params = hyper_params;
+    }
+
+    fn tick(&mut self) {
+        self.tick_with(ComputationType::Parallel);
+    }
+
+    fn iterate(&mut self, iterations: u32) {
+        for _ in 0..iterations {
+            self.tick();
+        }
+    }
+}
 
-        let mut edges: HashMap<u32, NeigbourIndeces2D> = HashMap::new(); // TODO: convert to array
+impl Universe2D {
+    /**
+     * Create a toroidal grid of `size * size` nodes without any agents
+     */
+    fn empty(size: u32) -> Universe2D {
+        // neighbours of node y * size + x
+        let mut edges = Vec::with_capacity((size * size) as usize);
 
         for y in 0..size {
             for x in 0..size {
-                let index = y * size + x;
-
                 let left_index = y * size + (x + size - 1) % size;
                 let right_index = y * size + (x + 1) % size;
                 let top_index = (y + size - 1) % size * size + x;
@@ -36,140 +135,638 @@ impl Universe for Universe2D {
                 let new_edges =
                     NeigbourIndeces2D::new(top_index, right_index, bottom_index, left_index);
 
-                edges.insert(index, new_edges);
+                edges.push(new_edges);
             }
         }
 
-        let mut nodes: Vec<Node2D> = (0..(size * size))
+        let nodes: Vec<Node2D> = (0..(size * size))
             .map(|index| Node2D::new(index, &edges))
             .collect();
 
-        // Set initial agents
-        (0..agent_size * 2).for_each(|id| {
-            let node_index = prng.rand_range(0..(size * size));
-            let species = if id % 2 == 0 {
-                AgentSpecies::Red
-            } else {
-                AgentSpecies::Blue
-            };
-
-            nodes[node_index as usize].add_agents(1, species);
-        });
-
         Universe2D {
             size,
             nodes,
             iteration: 0,
             hyper_params: HyperParams::default(),
+            cell_nodes: None,
+            movement_rule: Arc::new(PushWeighted),
+            occupancy: None,
+            trajectories: BTreeMap::new(),
+            visit_counts: None,
+            residence_counts: None,
+            delta_recording: None,
+            injections: Vec::new(),
+            birth_death: None,
+            display_mode: DisplayMode::default(),
+            temperature: 1.0,
+            schedule: None,
         }
     }
 
-    fn set_hyper_params(&mut self, hyper_params: HyperParams) {
-        self.hyper_params = hyper_params;
-    }
+    /**
+     * Perform a tick, running the passes over the nodes serially or in parallel
+     */
+    pub fn tick_with(&mut self, computation: ComputationType) {
+        #[cfg(debug_assertions)]
+        let agents_before = self.total_agents();
+
+        if let Some(schedule) = &self.schedule {
+            self.hyper_params = schedule(self.iteration);
+        }
 
-    fn tick(&mut self) {
         // 0) update graffiti in nodes
-        self.nodes.par_iter_mut().for_each(|node| {
-            node.update_graffiti_and_push_strength(&self.hyper_params, self.size);
+        let (hyper_params, size) = (self.push_params(), self.size);
+        for_each_node(&mut self.nodes, computation, |node| {
+            node.update_graffiti_and_push_strength(&hyper_params, size);
         });
         let nodes_with_graffiti = self.nodes.clone();
 
         // 1) move agents out
-        self.nodes.par_iter_mut().for_each(|node| {
-            node.move_agents_out(&nodes_with_graffiti, self.size);
+        let movement_rule = self.movement_rule.as_ref();
+        for_each_node(&mut self.nodes, computation, |node| {
+            node.move_agents_out(&nodes_with_graffiti, &hyper_params, movement_rule);
         });
 
         // 2) move agents in
         let nodes_with_agents_out = self.nodes.clone();
-        self.nodes.par_iter_mut().for_each(|node| {
+        for_each_node(&mut self.nodes, computation, |node| {
             node.move_agents_in(&nodes_with_agents_out);
         });
+        self.move_tracked_agents(&nodes_with_agents_out, 0);
+        self.count_residence(&nodes_with_agents_out);
+
+        // 3) further hops, the agents keep travelling in the direction they arrived in
+        for hop in 1..hyper_params.hop_distance {
+            for_each_node(&mut self.nodes, computation, |node| {
+                node.agents_out = node.agents_in;
+            });
+            let nodes_with_agents_out = self.nodes.clone();
+            for_each_node(&mut self.nodes, computation, |node| {
+                node.move_agents_in(&nodes_with_agents_out);
+            });
+            self.move_tracked_agents(&nodes_with_agents_out, hop);
+        }
 
         self.iteration += 1;
+
+        #[cfg(debug_assertions)]
+        self.check_invariants(agents_before);
+
+        self.apply_birth_death();
+        self.inject_agents();
+        self.record_occupancy();
+        self.count_visits();
+        self.record_delta();
     }
 
-    fn iterate(&mut self, iterations: u32) {
-        for _ in 0..iterations {
+    /**
+     * Tick with the nodes processed in parallel blocks of `chunk_size` contiguous nodes, see
+     * `ComputationType::Chunked`. Gives the same result as `tick`; the best `chunk_size` depends
+     * on the machine, a few rows of the grid (a multiple of `size`) is a good start.
+     *
+     * No extra read/write buffer is needed for the chunks: the graffiti pass only reads the node
+     * it writes, and the passes moving agents read the neighbours from a copy of the nodes taken
+     * after the previous pass, so a chunk never sees the writes of another one. The benchmark
+     * `tick_chunked_benchmark` compares chunk sizes with the default rayon partition.
+     */
+    pub fn tick_chunked(&mut self, chunk_size: usize) {
+        assert!(chunk_size > 0, "the chunk size must be at least 1");
+        self.tick_with(ComputationType::Chunked(chunk_size));
+    }
+
+    /**
+     * Tick until `budget` of wall-clock time has passed and return the number of ticks done,
+     * e.g. to fit as many ticks as possible in a frame. The clock is only checked between
+     * ticks, so the last tick may run past the budget.
+     */
+    #[cfg(feature = "std")]
+    pub fn tick_for(&mut self, budget: Duration) -> u32 {
+        let start = Instant::now();
+        let mut ticks = 0;
+        while start.elapsed() < budget {
             self.tick();
+            ticks += 1;
+        }
+        ticks
+    }
+
+    /**
+     * Choose what `Display` draws for every node, `DisplayMode::GraffitiDominance` by default
+     */
+    pub fn set_display_mode(&mut self, mode: DisplayMode) {
+        self.display_mode = mode;
+    }
+
+    /**
+     * Set the temperature T of the movement, which turns the push strength into the Boltzmann
+     * factor exp(-beta * graffiti / T), and recalculate the push strengths. Low temperatures
+     * send agents almost deterministically to the neighbour with the least opposing graffiti,
+     * high temperatures make the movement uniform. T = 1, the default, is the original model.
+     */
+    pub fn set_temperature(&mut self, t: f32) -> Result<(), UniverseError> {
+        if t.is_nan() || t <= 0.0 {
+            return Err(UniverseError::InvalidTemperature { temperature: t });
+        }
+
+        self.temperature = t;
+        let (hyper_params, size) = (self.push_params(), self.size);
+        for_each_node(&mut self.nodes, ComputationType::Parallel, |node| {
+            node.update_push_strength(&hyper_params, size);
+        });
+        Ok(())
+    }
+
+    /**
+     * The hyper parameters used for the push strength, with beta scaled by the temperature
+     */
+    fn push_params(&self) -> HyperParams {
+        HyperParams {
+            beta: self.hyper_params.beta / self.temperature,
+            ..self.hyper_params
+        }
+    }
+
+    /**
+     * Replace the rule deciding where agents move, `PushWeighted` by default
+     */
+    pub fn set_movement_rule<R: MovementRule + 'static>(&mut self, movement_rule: R) {
+        self.movement_rule = Arc::new(movement_rule);
+    }
+
+    /**
+     * Like `set_movement_rule`, for a rule that is already shared
+     */
+    #[cfg(feature = "std")]
+    pub(super) fn set_shared_movement_rule(&mut self, movement_rule: Arc<dyn MovementRule>) {
+        self.movement_rule = movement_rule;
+    }
+
+    #[cfg(debug_assertions)]
+    fn total_agents(&self) -> u32 {
+        self.nodes
+            .iter()
+            .map(|node| node.red_agents + node.blue_agents)
+            .sum()
+    }
+
+    /**
+     * Self-check run after every tick in debug builds: agents are conserved and all graffiti
+     * and push strengths are finite
+     */
+    #[cfg(debug_assertions)]
+    fn check_invariants(&self, expected_agents: u32) {
+        for node in &self.nodes {
+            assert!(
+                node.graffiti.red.is_finite() && node.graffiti.blue.is_finite(),
+                "graffiti of node {} is not finite",
+                node.index
+            );
+            assert!(
+                node.push_strength.red.is_finite() && node.push_strength.blue.is_finite(),
+                "push strength of node {} is not finite",
+                node.index
+            );
+        }
+
+        assert_eq!(
+            self.total_agents(),
+            expected_agents,
+            "agents not conserved in iteration {}",
+            self.iteration
+        );
+    }
+
+    /**
+     * Collect the standard statistics of the current state
+     */
+    #[cfg(feature = "std")]
+    pub fn tick_stats(&self) -> TickStats {
+        let mut stats = TickStats {
+            iteration: self.iteration,
+            red_agents: 0,
+            blue_agents: 0,
+            red_graffiti: 0.0,
+            blue_graffiti: 0.0,
+            segregation_index: self.segregation_index(),
+            energy: self.energy(),
+            species_boundary_length: self.species_boundary_length(),
+            flow_entropy: self.flow_entropy(),
+        };
+
+        for node in &self.nodes {
+            stats.red_agents += node.red_agents;
+            stats.blue_agents += node.blue_agents;
+            stats.red_graffiti += node.graffiti.red;
+            stats.blue_graffiti += node.graffiti.blue;
+        }
+
+        stats
+    }
+
+    /**
+     * Perform a tick and return its stats together with a custom measurement of the post-tick state
+     *
+     * # Examples
+     * ```
+     * use graph_walker::universe::{Universe, Universe2D};
+     * let mut universe = Universe2D::new(4, 100);
+     * let (stats, iteration) = universe.tick_with_measurement(|u| u.tick_stats().iteration);
+     * assert_eq!(stats.iteration, iteration);
+     * ```
+     */
+    #[cfg(feature = "std")]
+    pub fn tick_with_measurement<T, F: Fn(&Universe2D) -> T>(
+        &mut self,
+        measure: F,
+    ) -> (TickStats, T) {
+        self.tick();
+        (self.tick_stats(), measure(self))
+    }
+
+    /**
+     * Jump the graffiti `n` ticks ahead in a single pass, holding the agents in place, then
+     * recalculate the push strengths once. Equivalent to `n` graffiti updates without any
+     * movement; the flat per-node loop leaves vectorisation to the compiler.
+     * Neither the agents nor the iteration counter change.
+     */
+    pub fn tick_batch(&mut self, n: u32) {
+        if n == 0 {
+            return;
+        }
+
+        let hyper_params = self.push_params();
+        let size = self.size;
+        for_each_node(&mut self.nodes, ComputationType::Parallel, |node| {
+            node.advance_graffiti(&hyper_params, size, n);
+            node.update_push_strength(&hyper_params, size);
+        });
+    }
+
+    /**
+     * Run up to `iterations` graffiti updates with the agents held in place, stopping early
+     * once no graffiti changes by more than `RELAX_TOLERANCE`. The graffiti approaches the
+     * fixed point ξ = 𝛾 n / (ƛ l^2) of the update, which makes a settled substrate to release
+     * agents on. Neither the agents nor the iteration counter change.
+     */
+    pub fn relax_graffiti(&mut self, iterations: u32) {
+        let (hyper_params, size) = (self.push_params(), self.size);
+
+        for _ in 0..iterations {
+            let before: Vec<_> = self.nodes.iter().map(|node| node.graffiti).collect();
+            for_each_node(&mut self.nodes, ComputationType::Parallel, |node| {
+                node.update_graffiti_and_push_strength(&hyper_params, size);
+            });
+            let max_change = self
+                .nodes
+                .iter()
+                .zip(before)
+                .map(|(node, before)| {
+                    (node.graffiti.red - before.red)
+                        .abs()
+                        .max((node.graffiti.blue - before.blue).abs())
+                })
+                .fold(0.0, f32::max);
+
+            if max_change <= RELAX_TOLERANCE {
+                break;
+            }
         }
     }
+
+    /**
+     * One explicit diffusion step on the graffiti: the graffiti of every active node becomes
+     * (1 - `weight`) times its own plus `weight` times the mean over its four neighbours, after
+     * which the push strengths are recalculated. Edges bent back by walls count the node
+     * itself, so the total graffiti is conserved.
+     */
+    pub fn smooth_graffiti(&mut self, weight: f32) {
+        let graffiti: Vec<_> = self.nodes.iter().map(|node| node.graffiti).collect();
+        let (hyper_params, size) = (self.push_params(), self.size);
+
+        for_each_node(&mut self.nodes, ComputationType::Parallel, |node| {
+            if !node.active {
+                return;
+            }
+
+            let (mut red, mut blue) = (0.0, 0.0);
+            for neighbour_idx in node.neighbours {
+                red += graffiti[neighbour_idx as usize].red;
+                blue += graffiti[neighbour_idx as usize].blue;
+            }
+            node.graffiti.red = (1.0 - weight) * node.graffiti.red + weight * red / 4.0;
+            node.graffiti.blue = (1.0 - weight) * node.graffiti.blue + weight * blue / 4.0;
+            node.update_push_strength(&hyper_params, size);
+        });
+    }
+
+    /**
+     * Position in `nodes` of the node at (x, y), `None` if the cell was removed by `compact`
+     */
+    fn node_index(&self, x: u32, y: u32) -> Option<usize> {
+        let cell = y * self.size + x;
+        match &self.cell_nodes {
+            None => Some(cell as usize),
+            Some(cell_nodes) => match cell_nodes[cell as usize] {
+                u32::MAX => None,
+                index => Some(index as usize),
+            },
+        }
+    }
+
+    /**
+     * Grid cell (y * size + x) of the node at position `index` in `nodes`
+     */
+    fn node_cell(&self, index: usize) -> u32 {
+        self.nodes[index].cell
+    }
+
+    /**
+     * Rebuild the lookup from grid cells to nodes after nodes were removed or reordered
+     */
+    fn update_cell_nodes(&mut self) {
+        let in_place = self.nodes.len() == (self.size * self.size) as usize
+            && self
+                .nodes
+                .iter()
+                .enumerate()
+                .all(|(index, node)| node.cell == index as u32);
+        if in_place {
+            self.cell_nodes = None;
+            return;
+        }
+
+        let mut cell_nodes = vec![u32::MAX; (self.size * self.size) as usize];
+        for (index, node) in self.nodes.iter().enumerate() {
+            // cells outside the grid are left for `validate` to report
+            if let Some(slot) = cell_nodes.get_mut(node.cell as usize) {
+                *slot = index as u32;
+            }
+        }
+        self.cell_nodes = Some(cell_nodes);
+    }
+
+    /**
+     * The (red, blue) agent counts of the node at (x, y), (0, 0) for a removed cell
+     */
+    pub fn agents_at(&self, x: u32, y: u32) -> (u32, u32) {
+        match self.node_index(x, y) {
+            Some(index) => (self.nodes[index].red_agents, self.nodes[index].blue_agents),
+            None => (0, 0),
+        }
+    }
+
+    /**
+     * Overwrite the agent counts of every node from two row-major arrays of length `size * size`.
+     * Entries of removed cells are ignored. Graffiti and push strengths are left as they are and
+     * catch up on the next tick.
+     */
+    pub fn load_agents(&mut self, red: &[u32], blue: &[u32]) -> Result<(), UniverseError> {
+        let expected = (self.size * self.size) as usize;
+        for values in [red, blue] {
+            if values.len() != expected {
+                return Err(UniverseError::LengthMismatch {
+                    expected,
+                    found: values.len(),
+                });
+            }
+        }
+
+        for node in self.nodes.iter_mut() {
+            node.red_agents = red[node.cell as usize];
+            node.blue_agents = blue[node.cell as usize];
+        }
+        Ok(())
+    }
+
+    /**
+     * Add `per_cell` agents of `species` to every cell on the line from `from` to `to`, both
+     * included, rasterised with Bresenham's algorithm. The line takes the shorter way around the
+     * torus along each axis. Walls and removed cells on the line are skipped.
+     */
+    pub fn seed_line(
+        &mut self,
+        from: (u32, u32),
+        to: (u32, u32),
+        species: AgentSpecies,
+        per_cell: u32,
+    ) {
+        let size = self.size as i64;
+        for (x, y) in [from, to] {
+            assert!(
+                (x as i64) < size && (y as i64) < size,
+                "({}, {}) is outside the grid",
+                x,
+                y
+            );
+        }
+
+        // shortest signed displacement on the torus
+        let wrapped_delta = |from: u32, to: u32| {
+            let delta = (to as i64 - from as i64).rem_euclid(size);
+            if delta > size / 2 {
+                delta - size
+            } else {
+                delta
+            }
+        };
+        let (dx, dy) = (wrapped_delta(from.0, to.0), wrapped_delta(from.1, to.1));
+        let (step_x, step_y) = (dx.signum(), dy.signum());
+        let (dx, dy) = (dx.abs(), -dy.abs());
+
+        let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+        let mut error = dx + dy;
+        loop {
+            let cell = (x.rem_euclid(size) as u32, y.rem_euclid(size) as u32);
+            if let Some(index) = self.node_index(cell.0, cell.1) {
+                if self.nodes[index].active {
+                    self.nodes[index].add_agents(per_cell, species);
+                }
+            }
+            if cell == to {
+                break;
+            }
+
+            let doubled_error = 2 * error;
+            if doubled_error >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled_error <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /**
+     * The species whose graffiti dominates the node at (x, y), if any
+     */
+    pub fn dominant_species(&self, x: u32, y: u32) -> Option<AgentSpecies> {
+        self.nodes[self.node_index(x, y)?].dominant_species()
+    }
+
+    /**
+     * Iterate over all active nodes together with their (x, y) grid coordinates
+     */
+    #[cfg(feature = "std")]
+    fn nodes_iter(&self) -> impl Iterator<Item = (u32, u32, &Node2D)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.active)
+            .map(move |(index, node)| {
+                let cell = self.node_cell(index);
+                (cell % self.size, cell / self.size, node)
+            })
+    }
+}
+
+/**
+ * Apply `f` to every node, serially, in parallel or in parallel chunks. Without `std` there is
+ * no rayon and every computation runs serially.
+ */
+fn for_each_node<F: Fn(&mut Node2D) + Sync + Send>(
+    nodes: &mut [Node2D],
+    computation: ComputationType,
+    f: F,
+) {
+    match computation {
+        ComputationType::Serial => nodes.iter_mut().for_each(f),
+        #[cfg(feature = "std")]
+        ComputationType::Parallel => nodes.par_iter_mut().for_each(f),
+        #[cfg(feature = "std")]
+        ComputationType::Chunked(chunk_size) => nodes
+            .par_chunks_mut(chunk_size)
+            .for_each(|chunk| chunk.iter_mut().for_each(&f)),
+        #[cfg(not(feature = "std"))]
+        ComputationType::Parallel | ComputationType::Chunked(_) => nodes.iter_mut().for_each(f),
+    }
 }
 
 impl fmt::Debug for Universe2D {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} UNIVERSE 2D {}\n", "=".repeat(10), "=".repeat(10))?;
+        writeln!(f, "{} UNIVERSE 2D {}", "=".repeat(10), "=".repeat(10))?;
 
-        write!(f, "size: {}\n", self.size)?;
-        write!(f, "node size: {}\n", self.nodes.len())?;
-        write!(f, "iterations: {}\n", self.iteration)?;
+        writeln!(f, "size: {}", self.size)?;
+        writeln!(f, "node size: {}", self.nodes.len())?;
+        writeln!(f, "iterations: {}", self.iteration)?;
 
-        write!(f, "{}\n", "=".repeat(30))?;
+        writeln!(f, "{}", "=".repeat(30))?;
         for y in 0..self.size {
             for x in 0..self.size {
-                let index = y * self.size + x;
-                let node = &self.nodes[index as usize];
+                let Some(index) = self.node_index(x, y) else {
+                    write!(f, "|{}", " ".repeat(25))?;
+                    continue;
+                };
+                let node = &self.nodes[index];
 
-                let blue_agents =
-                    self.nodes[index as usize].get_agents_with_species(&AgentSpecies::Blue);
-                let red_agents =
-                    self.nodes[index as usize].get_agents_with_species(&AgentSpecies::Red);
+                let blue_agents = node.get_agents_with_species(&AgentSpecies::Blue);
+                let red_agents = node.get_agents_with_species(&AgentSpecies::Red);
 
                 let blue_graffiti = node.blue_agents;
                 let red_graffiti = node.red_agents;
 
+                // padded or cut to exactly the width, like `pad`'s `with_exact_width`
                 write!(
                     f,
-                    "|{} a({},{}) g:({},{})",
-                    index.to_string().with_exact_width(2),
-                    blue_agents.to_string().with_exact_width(2),
-                    red_agents.to_string().with_exact_width(2),
-                    blue_graffiti.to_string().with_exact_width(4),
-                    red_graffiti.to_string().with_exact_width(4)
+                    "|{:<2.2} a({:<2.2},{:<2.2}) g:({:<4.4},{:<4.4})",
+                    index.to_string(),
+                    blue_agents.to_string(),
+                    red_agents.to_string(),
+                    blue_graffiti.to_string(),
+                    red_graffiti.to_string()
                 )?;
             }
-            write!(f, "|\n")?;
+            writeln!(f, "|")?;
         }
         write!(f, "")
     }
 }
 
+#[cfg(feature = "std")]
 impl fmt::Display for Universe2D {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} UNIVERSE 2D {}\n", "=".repeat(10), "=".repeat(10))?;
+        writeln!(f, "{} UNIVERSE 2D {}", "=".repeat(10), "=".repeat(10))?;
+
+        writeln!(f, "size: {}", self.size)?;
+        writeln!(f, "node size: {}", self.nodes.len())?;
+        writeln!(f, "iterations: {}", self.iteration)?;
 
-        write!(f, "size: {}\n", self.size)?;
-        write!(f, "node size: {}\n", self.nodes.len())?;
-        write!(f, "iterations: {}\n", self.iteration)?;
+        writeln!(f, "{}", "=".repeat(30))?;
+        let max_agents = self
+            .nodes
+            .iter()
+            .map(|node| node.red_agents + node.blue_agents)
+            .max()
+            .unwrap_or(0);
+        let max_graffiti = self
+            .nodes
+            .iter()
+            .map(|node| node.graffiti.red + node.graffiti.blue)
+            .fold(0.0, f32::max);
 
-        write!(f, "{}\n", "=".repeat(30))?;
         for y in 0..self.size {
             for x in 0..self.size {
-                let index = y * self.size + x;
-                let node = &self.nodes[index as usize];
-
-                let blue_graffiti = node.graffiti.blue;
-                let red_graffiti = node.graffiti.red;
-
-                let delta = blue_graffiti - red_graffiti;
-
-                if delta.abs() < 0.1 {
-                    write!(f, "🟩")?;
-                } else if delta > 0.0 {
-                    write!(f, "🟦")?;
-                } else {
-                    write!(f, "🟥")?;
+                let Some(index) = self.node_index(x, y) else {
+                    write!(f, "⬛")?;
+                    continue;
+                };
+                let node = &self.nodes[index];
+                if !node.active {
+                    write!(f, "⬛")?;
+                    continue;
                 }
+
+                let symbol = match self.display_mode {
+                    DisplayMode::GraffitiDominance => match node.dominant_species() {
+                        None => "🟩",
+                        Some(AgentSpecies::Blue) => "🟦",
+                        Some(AgentSpecies::Red) => "🟥",
+                    },
+                    DisplayMode::AgentCount => shade(
+                        (node.red_agents + node.blue_agents) as f32,
+                        max_agents as f32,
+                    ),
+                    DisplayMode::PushStrength => {
+                        let (red, blue) = (node.push_strength.red, node.push_strength.blue);
+                        match red / (red + blue) {
+                            ratio if ratio.is_nan() => "⬜",
+                            ratio if ratio < 0.2 => "🟦",
+                            ratio if ratio < 0.4 => "🔵",
+                            ratio if ratio <= 0.6 => "🟪",
+                            ratio if ratio <= 0.8 => "🔴",
+                            _ => "🟥",
+                        }
+                    }
+                    DisplayMode::GraffitiValue => {
+                        shade(node.graffiti.red + node.graffiti.blue, max_graffiti)
+                    }
+                };
+                write!(f, "{}", symbol)?;
             }
-            write!(f, "|\n")?;
+            writeln!(f, "|")?;
         }
         write!(f, "")
     }
 }
 
+/**
+ * Two characters wide block, darker as `value` approaches `max`
+ */
+#[cfg(feature = "std")]
+fn shade(value: f32, max: f32) -> &'static str {
+    const SHADES: [&str; 5] = ["  ", "░░", "▒▒", "▓▓", "██"];
+
+    if max <= 0.0 || value <= 0.0 {
+        return SHADES[0];
+    }
+    let level = (value / max * (SHADES.len() - 1) as f32).ceil() as usize;
+    SHADES[level.min(SHADES.len() - 1)]
+}
+
 #[cfg(test)]
 mod test_2d_universe {
-    use crate::agent_species::AgentSpecies;
+    use crate::{agent_species::AgentSpecies, hyper_params::Interaction};
 
     use super::*;
 
@@ -265,4 +862,518 @@ mod test_2d_universe {
             });
         println!("universe_hash_i: {}", universe_hash_i);
     }
+
+    #[test]
+    fn test_tick_with_measurement() {
+        let mut universe = Universe2D::new(4, 100);
+
+        let (stats, measured_agents) = universe.tick_with_measurement(total_agent_size);
+
+        assert_eq!(stats.iteration, 1);
+        assert_eq!(stats.red_agents, 100);
+        assert_eq!(stats.blue_agents, 100);
+        assert_eq!(measured_agents, 200);
+        assert!(stats.red_graffiti > 0.0);
+        assert!(stats.blue_graffiti > 0.0);
+    }
+
+    #[test]
+    fn test_tick_batch() {
+        let mut batched = Universe2D::new(8, 300);
+        batched.set_hyper_params(HyperParams::new(0.3, 0.2, 0.05));
+        batched.tick();
+        let mut sequential = batched.clone();
+
+        batched.tick_batch(15);
+        for _ in 0..15 {
+            sequential.nodes.iter_mut().for_each(|node| {
+                node.update_graffiti_and_push_strength(&sequential.hyper_params, 8);
+            });
+        }
+
+        for (a, b) in batched.nodes.iter().zip(sequential.nodes.iter()) {
+            assert_eq!(a.red_agents, b.red_agents);
+            assert_eq!(a.blue_agents, b.blue_agents);
+            assert!((a.graffiti.red - b.graffiti.red).abs() < 1e-3);
+            assert!((a.graffiti.blue - b.graffiti.blue).abs() < 1e-3);
+            assert!((a.push_strength.red - b.push_strength.red).abs() < 1e-4);
+            assert!((a.push_strength.blue - b.push_strength.blue).abs() < 1e-4);
+        }
+        assert_eq!(batched.iteration, 1);
+    }
+
+    #[test]
+    fn test_tick_for() {
+        let mut universe = Universe2D::new(32, 5000);
+        assert_eq!(universe.tick_for(Duration::ZERO), 0);
+
+        let start = Instant::now();
+        let ticks = universe.tick_for(Duration::from_micros(1));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!((1..=3).contains(&ticks), "{} ticks", ticks);
+        assert_eq!(universe.iteration, ticks);
+    }
+
+    #[test]
+    fn test_display_modes() {
+        let mut universe = Universe2D::new(5, 40);
+        let mut active = vec![true; 25];
+        active[12] = false;
+        universe.apply_mask(&active).unwrap();
+        universe.iterate(3);
+
+        for mode in [
+            DisplayMode::GraffitiDominance,
+            DisplayMode::AgentCount,
+            DisplayMode::PushStrength,
+            DisplayMode::GraffitiValue,
+        ] {
+            universe.set_display_mode(mode);
+            let output = format!("{}", universe);
+
+            let rows: Vec<&str> = output.lines().filter(|line| line.ends_with('|')).collect();
+            assert_eq!(rows.len(), 5, "{:?}", mode);
+            assert!(rows.iter().all(|row| !row.trim_end_matches('|').is_empty()));
+            assert!(rows[2].contains('⬛'), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn test_tick_chunked_matches_tick() {
+        let mut universe = Universe2D::new(10, 300);
+        let mut chunked = universe.clone();
+        let mut uneven = universe.clone();
+
+        for _ in 0..5 {
+            universe.tick();
+            chunked.tick_chunked(20);
+            uneven.tick_chunked(7);
+        }
+
+        assert_eq!(chunked.state_fingerprint(), universe.state_fingerprint());
+        assert_eq!(uneven.state_fingerprint(), universe.state_fingerprint());
+    }
+
+    #[test]
+    fn test_seed_line() {
+        let mut universe = Universe2D::new(8, 0);
+        universe.seed_line((1, 3), (5, 3), AgentSpecies::Red, 4);
+        for x in 0..8 {
+            let expected = if (1..=5).contains(&x) { 4 } else { 0 };
+            assert_eq!(universe.agents_at(x, 3), (expected, 0));
+        }
+        assert_eq!(universe.tick_stats().red_agents, 5 * 4);
+
+        // wraps around the right edge rather than crossing the grid
+        let mut wrapped = Universe2D::new(8, 0);
+        wrapped.seed_line((6, 0), (1, 2), AgentSpecies::Blue, 2);
+        assert_eq!(wrapped.tick_stats().blue_agents, 4 * 2);
+        assert_eq!(wrapped.agents_at(6, 0), (0, 2));
+        assert_eq!(wrapped.agents_at(1, 2), (0, 2));
+        assert_eq!(wrapped.agents_at(3, 1), (0, 0));
+
+        let mut diagonal = Universe2D::new(8, 0);
+        diagonal.seed_line((2, 2), (2, 2), AgentSpecies::Red, 1);
+        diagonal.seed_line((0, 0), (3, 3), AgentSpecies::Blue, 1);
+        assert_eq!(diagonal.agents_at(2, 2), (1, 1));
+        assert_eq!(diagonal.tick_stats().blue_agents, 4);
+    }
+
+    #[test]
+    fn test_relax_graffiti_reaches_fixed_point() {
+        let mut universe = Universe2D::new(6, 0);
+        let red: Vec<u32> = (0..36).map(|index| index % 5).collect();
+        let blue: Vec<u32> = (0..36).map(|index| index % 3).collect();
+        universe.load_agents(&red, &blue).unwrap();
+        universe.set_hyper_params(HyperParams::new(0.4, 0.3, 0.05));
+
+        universe.relax_graffiti(500);
+
+        for (node, (red, blue)) in universe.nodes.iter().zip(red.iter().zip(&blue)) {
+            assert!((node.graffiti.red - 0.4 * *red as f32 / 0.3).abs() < 1e-4);
+            assert!((node.graffiti.blue - 0.4 * *blue as f32 / 0.3).abs() < 1e-4);
+            assert_eq!((node.red_agents, node.blue_agents), (*red, *blue));
+        }
+        assert_eq!(universe.iteration, 0);
+    }
+
+    #[test]
+    fn test_smooth_graffiti_spreads_spike() {
+        let mut universe = Universe2D::new(5, 0);
+        let spike = universe.node_index(2, 2).unwrap();
+        universe.nodes[spike].graffiti.red = 8.0;
+        let push_before = universe.nodes[spike].push_strength.red;
+
+        universe.smooth_graffiti(0.5);
+
+        let graffiti_at = |x, y| {
+            universe.nodes[universe.node_index(x, y).unwrap()]
+                .graffiti
+                .red
+        };
+        assert_eq!(graffiti_at(2, 2), 4.0);
+        for (x, y) in [(2, 1), (3, 2), (2, 3), (1, 2)] {
+            assert_eq!(graffiti_at(x, y), 1.0);
+        }
+        assert_eq!(graffiti_at(0, 0), 0.0);
+        assert_eq!(universe.tick_stats().red_graffiti, 8.0);
+        assert_ne!(universe.nodes[spike].push_strength.red, push_before);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "agents not conserved")]
+    fn test_invariant_checker_detects_agent_loss() {
+        let mut universe = Universe2D::new(4, 100);
+        // corrupt the topology: node 0 now pulls in agents meant for another node
+        universe.nodes[0].neighbours.top = 5;
+
+        universe.tick();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "is not finite")]
+    fn test_invariant_checker_detects_non_finite_graffiti() {
+        let mut universe = Universe2D::new(4, 100);
+        universe.nodes[3].graffiti.blue = f32::NAN;
+
+        universe.tick();
+    }
+
+    #[test]
+    fn test_load_agents() {
+        let mut universe = Universe2D::new(3, 50);
+        let red: Vec<u32> = (0..9).collect();
+        let blue: Vec<u32> = (0..9).map(|i| 10 * i).collect();
+
+        universe.load_agents(&red, &blue).unwrap();
+
+        for y in 0..3 {
+            for x in 0..3 {
+                let i = y * 3 + x;
+                assert_eq!(universe.agents_at(x, y), (i, 10 * i));
+            }
+        }
+
+        assert_eq!(
+            universe.load_agents(&red, &blue[..4]),
+            Err(UniverseError::LengthMismatch {
+                expected: 9,
+                found: 4
+            })
+        );
+        assert_eq!(universe.agents_at(2, 2), (8, 80));
+    }
+
+    #[test]
+    fn test_push_strength_independent_of_grid_size_with_fixed_l0() {
+        let hyper_params = HyperParams {
+            l0: Some(0.5),
+            ..HyperParams::default()
+        };
+        let push_strengths = |size: u32| {
+            let mut universe = Universe2D::new(size, 0);
+            universe.set_hyper_params(hyper_params);
+            let cells = (size * size) as usize;
+            universe
+                .load_agents(&vec![3; cells], &vec![1; cells])
+                .unwrap();
+            universe.tick();
+            let node = &universe.nodes[0];
+            (node.push_strength.red, node.push_strength.blue)
+        };
+
+        assert_eq!(push_strengths(4), push_strengths(16));
+
+        let mut default_l0 = Universe2D::new(4, 0);
+        default_l0.load_agents(&[3; 16], &[1; 16]).unwrap();
+        default_l0.tick();
+        assert!(default_l0.nodes[0].push_strength.red > push_strengths(4).0);
+    }
+
+    struct AllToTheRight;
+
+    impl MovementRule for AllToTheRight {
+        fn distribute(&self, agents: u32, _push: &[f32; 4], _prng: &mut Rand32) -> [u32; 4] {
+            [0, agents, 0, 0]
+        }
+    }
+
+    #[test]
+    fn test_custom_movement_rule() {
+        let mut universe = Universe2D::new(5, 100);
+        universe.set_movement_rule(AllToTheRight);
+        let before = universe.clone();
+
+        universe.tick();
+
+        for y in 0..5 {
+            for x in 0..5 {
+                assert_eq!(universe.agents_at((x + 1) % 5, y), before.agents_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the movement rule must move every")]
+    fn test_movement_rule_must_move_every_agent() {
+        struct DropsAgents;
+
+        impl MovementRule for DropsAgents {
+            fn distribute(&self, agents: u32, _push: &[f32; 4], _prng: &mut Rand32) -> [u32; 4] {
+                [agents / 2, 0, 0, 0]
+            }
+        }
+
+        let mut universe = Universe2D::new(5, 100);
+        universe.set_movement_rule(DropsAgents);
+        universe.tick_with(ComputationType::Serial);
+    }
+
+    #[test]
+    fn test_serial_tick_matches_parallel_tick() {
+        let mut parallel = Universe2D::new(6, 150);
+        let mut serial = parallel.clone();
+
+        for _ in 0..5 {
+            parallel.tick_with(ComputationType::Parallel);
+            serial.tick_with(ComputationType::Serial);
+        }
+
+        for (a, b) in parallel.nodes.iter().zip(serial.nodes.iter()) {
+            assert_eq!((a.red_agents, a.blue_agents), (b.red_agents, b.blue_agents));
+            assert_eq!(a.graffiti.red, b.graffiti.red);
+            assert_eq!(a.graffiti.blue, b.graffiti.blue);
+        }
+    }
+
+    #[test]
+    fn test_temperature_extremes() {
+        // red agents in the centre, fixed blue graffiti on the neighbours, least on the left
+        let after_one_tick = |temperature: f32| {
+            let mut universe = Universe2D::new(3, 0);
+            universe.set_hyper_params(HyperParams::new(0.0, 0.0, 1.0));
+            let mut red = [0; 9];
+            red[universe.node_index(1, 1).unwrap()] = 1000;
+            universe.load_agents(&red, &[0; 9]).unwrap();
+            for (x, y, graffiti) in [(1, 0, 1.0), (2, 1, 2.0), (1, 2, 3.0), (0, 1, 0.5)] {
+                let index = universe.node_index(x, y).unwrap();
+                universe.nodes[index].graffiti.blue = graffiti;
+            }
+
+            universe.set_temperature(temperature).unwrap();
+            universe.tick();
+            [(1, 0), (2, 1), (1, 2), (0, 1)]
+                .map(|(x, y)| universe.nodes[universe.node_index(x, y).unwrap()].red_agents)
+        };
+
+        // cold: every agent moves to the neighbour with the least graffiti
+        assert_eq!(after_one_tick(0.01), [0, 0, 0, 1000]);
+
+        // hot: the graffiti no longer matters
+        for agents in after_one_tick(f32::INFINITY) {
+            assert!((150..350).contains(&agents), "{} agents", agents);
+        }
+    }
+
+    #[test]
+    fn test_set_temperature() {
+        let mut universe = Universe2D::new(4, 10);
+        universe.iterate(3);
+        let push_strength = universe.nodes[0].push_strength;
+
+        for invalid in [0.0, -1.0, f32::NAN] {
+            assert!(matches!(
+                universe.set_temperature(invalid),
+                Err(UniverseError::InvalidTemperature { .. })
+            ));
+        }
+        assert_eq!(universe.temperature, 1.0);
+
+        universe.set_temperature(2.0).unwrap();
+        assert!(universe.nodes[0].push_strength.red >= push_strength.red);
+        universe.set_temperature(1.0).unwrap();
+        assert_eq!(universe.nodes[0].push_strength.red, push_strength.red);
+    }
+
+    #[test]
+    fn test_mass_scales_graffiti_deposition() {
+        let mut universe = Universe2D::new(4, 0);
+        universe.set_hyper_params(HyperParams {
+            blue_mass: 3.0,
+            ..HyperParams::default()
+        });
+        universe.load_agents(&[5; 16], &[5; 16]).unwrap();
+
+        universe.tick();
+
+        let stats = universe.tick_stats();
+        assert!(stats.red_graffiti > 0.0);
+        assert!((stats.blue_graffiti - 3.0 * stats.red_graffiti).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_hop_distance_skips_cells() {
+        let mut universe = Universe2D::new(5, 0);
+        universe.set_hyper_params(HyperParams {
+            hop_distance: 2,
+            ..HyperParams::default()
+        });
+        let mut red = [0; 25];
+        red[universe.node_index(2, 2).unwrap()] = 1000;
+        universe.load_agents(&red, &[0; 25]).unwrap();
+
+        universe.tick();
+
+        // every agent lands two cells away in a straight line
+        let landed: u32 = [(2, 0), (4, 2), (2, 4), (0, 2)]
+            .iter()
+            .map(|&(x, y)| universe.agents_at(x, y).0)
+            .sum();
+        assert_eq!(landed, 1000);
+        assert_eq!(universe.agents_at(2, 1), (0, 0));
+        assert_eq!(universe.agents_at(2, 2), (0, 0));
+
+        // agents are conserved when hops run into walls
+        let mut universe = Universe2D::new_fractal(9, 2, 3, HyperParams::default());
+        universe.set_hyper_params(HyperParams {
+            hop_distance: 3,
+            ..HyperParams::default()
+        });
+        universe.iterate(10);
+        assert_eq!(total_agent_size(&universe), 2 * 3 * 64);
+        assert_eq!(universe.agents_at(4, 4), (0, 0));
+    }
+
+    #[test]
+    fn test_pheromone_decays_faster_than_graffiti() {
+        let mut universe = Universe2D::new(4, 0);
+        let hyper_params = HyperParams {
+            gamma2: 0.5,
+            lambda2: 0.5,
+            pheromone_weight: 1.0,
+            ..HyperParams::new(0.5, 0.1, 0.2)
+        };
+        universe.set_hyper_params(hyper_params);
+        universe.load_agents(&[5; 16], &[0; 16]).unwrap();
+        universe.iterate(20);
+        let before = universe.nodes[0].clone();
+        assert!(before.pheromone.red > 0.0);
+
+        // the pheromone lowers the push strength on top of the graffiti
+        let mut without_pheromone = before.clone();
+        without_pheromone.update_push_strength(
+            &HyperParams {
+                pheromone_weight: 0.0,
+                ..hyper_params
+            },
+            4,
+        );
+        assert!(before.push_strength.red < without_pheromone.push_strength.red);
+
+        universe.load_agents(&[0; 16], &[0; 16]).unwrap();
+        universe.iterate(5);
+        let after = &universe.nodes[0];
+        let pheromone_left = after.pheromone.red / before.pheromone.red;
+        let graffiti_left = after.graffiti.red / before.graffiti.red;
+        assert!((pheromone_left - 0.5f32.powi(5)).abs() < 1e-5);
+        assert!((graffiti_left - 0.9f32.powi(5)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_persistence_spreads_agents_further() {
+        let mean_squared_displacement = |persistence: f32| {
+            let size = 31;
+            let mut universe = Universe2D::new(size, 0);
+            universe.set_hyper_params(HyperParams {
+                persistence,
+                ..HyperParams::default()
+            });
+            let mut red = vec![0; (size * size) as usize];
+            red[universe.node_index(15, 15).unwrap()] = 2000;
+            universe.load_agents(&red, &vec![0; red.len()]).unwrap();
+
+            universe.iterate(8);
+
+            let squared_displacement: u32 = universe
+                .nodes_iter()
+                .map(|(x, y, node)| {
+                    node.red_agents * (x.abs_diff(15).pow(2) + y.abs_diff(15).pow(2))
+                })
+                .sum();
+            squared_displacement as f32 / 2000.0
+        };
+
+        let random_walk = mean_squared_displacement(0.0);
+        let persistent_walk = mean_squared_displacement(5.0);
+
+        // an unbiased random walk spreads out one node^2 per tick
+        assert!((random_walk - 8.0).abs() < 1.0, "{}", random_walk);
+        assert!(persistent_walk > 2.0 * random_walk, "{}", persistent_walk);
+    }
+
+    #[test]
+    fn test_predator_tracks_prey() {
+        // red starts left of blue, (predator x, distance between the centroids) every 10 ticks
+        let track = |interaction: Interaction| {
+            let size = 32;
+            let mut universe =
+                Universe2D::new_from_gaussian(size, 12.0, 16.0, 3.0, 2000, AgentSpecies::Red, 1);
+            universe.add_gaussian_agents(20.0, 16.0, 3.0, 2000, AgentSpecies::Blue, 2);
+            universe.set_hyper_params(HyperParams {
+                interaction,
+                ..HyperParams::new(0.5, 0.5, 0.2)
+            });
+
+            (0..4)
+                .map(|_| {
+                    universe.iterate(10);
+                    let (red_x, red_y) = universe.agent_centroid(AgentSpecies::Red).unwrap();
+                    let (blue_x, blue_y) = universe.agent_centroid(AgentSpecies::Blue).unwrap();
+                    let dx = (red_x - blue_x)
+                        .abs()
+                        .min(size as f32 - (red_x - blue_x).abs());
+                    let dy = (red_y - blue_y)
+                        .abs()
+                        .min(size as f32 - (red_y - blue_y).abs());
+                    (red_x, (dx * dx + dy * dy).sqrt())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let avoid = track(Interaction::Avoid);
+        let chase = track(Interaction::PredatorPrey {
+            predator: AgentSpecies::Red,
+        });
+
+        // the predator keeps moving towards the fleeing prey and stays closer than when avoiding
+        assert!(chase[0].0 > 12.0, "{:?}", chase);
+        assert!(
+            chase.windows(2).all(|pair| pair[1].0 > pair[0].0),
+            "{:?}",
+            chase
+        );
+        assert!(avoid[3].0 < 12.0, "{:?}", avoid);
+        assert!(chase[3].1 < avoid[3].1, "{:?} {:?}", chase, avoid);
+    }
+
+    #[test]
+    fn test_attraction_with_large_graffiti_stays_finite() {
+        let mut universe = Universe2D::new(8, 10000);
+        universe.set_hyper_params(HyperParams {
+            interaction: Interaction::Attract,
+            ..HyperParams::new(0.5, 0.01, 1.0)
+        });
+
+        // the graffiti climbs to thousands, far beyond where e^(beta * graffiti) overflows
+        universe.iterate(20);
+
+        assert!(universe.nodes.iter().any(|node| node.graffiti.red > 1000.0));
+        assert!(universe.nodes.iter().all(|node| {
+            node.push_strength.red.is_finite() && node.push_strength.blue.is_finite()
+        }));
+        assert_eq!(total_agent_size(&universe), 20000);
+    }
 }
@@ -0,0 +1,2372 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use oorandom::Rand32;
+use rayon::prelude::*;
+
+use crate::{
+    agent_species::AgentSpecies,
+    hyper_params::{HyperParams, SensitivityParam},
+    nodes::{Node, Node2D},
+    statistics::{circular_mean, quantile, Welford, WelfordCovariance},
+    universe::{
+        AgentFlow, ComputationType, EnsembleStats, MeasurementResult, TickStats, Universe,
+        Universe2D, UniverseError, UniverseState,
+    },
+};
+
+/**
+ * Share of the agents assumed for nodes that are empty in the reference distribution, so that
+ * the logarithms of the snapshot comparisons stay finite
+ */
+const SNAPSHOT_EPSILON: f64 = 1e-10;
+
+/**
+ * Agents added to both directions of every crossed pair of cells in `entropy_production_rate`,
+ * so that flows in only one direction contribute a finite amount
+ */
+const FLUX_PSEUDOCOUNT: f64 = 0.5;
+
+/**
+ * Probability per tick that an agent gives birth, and that an agent on a node at capacity
+ * dies, in the trials of `win_probability`
+ */
+const WIN_BIRTH_DEATH_RATE: f32 = 0.1;
+
+impl Universe2D {
+    /**
+     * Grid coordinates of all nodes where the graffiti of `species` exceeds `threshold`,
+     * sorted by graffiti value descending
+     */
+    pub fn hot_spots(&self, threshold: f32, species: AgentSpecies) -> Vec<(u32, u32)> {
+        let mut spots: Vec<(u32, u32, f32)> = self
+            .nodes_iter()
+            .map(|(x, y, node)| (x, y, node.get_graffiti(&species)))
+            .filter(|(_, _, graffiti)| *graffiti > threshold)
+            .collect();
+
+        spots.sort_by(|a, b| b.2.total_cmp(&a.2));
+        spots.into_iter().map(|(x, y, _)| (x, y)).collect()
+    }
+
+    /**
+     * Grid coordinates of all nodes where the graffiti of `species` is below `threshold`,
+     * sorted by graffiti value ascending
+     */
+    pub fn cold_spots(&self, threshold: f32, species: AgentSpecies) -> Vec<(u32, u32)> {
+        let mut spots: Vec<(u32, u32, f32)> = self
+            .nodes_iter()
+            .map(|(x, y, node)| (x, y, node.get_graffiti(&species)))
+            .filter(|(_, _, graffiti)| *graffiti < threshold)
+            .collect();
+
+        spots.sort_by(|a, b| a.2.total_cmp(&b.2));
+        spots.into_iter().map(|(x, y, _)| (x, y)).collect()
+    }
+
+    /**
+     * Grid coordinates of all cells whose dominant species or agent counts differ from `other`.
+     * Cells are paired by position, so walls and compacted universes line up; a wall counts
+     * as an empty cell.
+     */
+    pub fn diff(&self, other: &Universe2D) -> Result<Vec<(u32, u32)>, UniverseError> {
+        if self.size != other.size {
+            return Err(UniverseError::SizeMismatch {
+                expected: self.size,
+                found: other.size,
+            });
+        }
+
+        let cell_state = |universe: &Universe2D, x: u32, y: u32| match universe
+            .node_index(x, y)
+            .map(|index| &universe.nodes[index])
+        {
+            Some(node) if node.active => {
+                (node.red_agents, node.blue_agents, node.dominant_species())
+            }
+            _ => (0, 0, None),
+        };
+
+        let size = self.size;
+        Ok((0..size * size)
+            .map(|cell| (cell % size, cell / size))
+            .filter(|&(x, y)| cell_state(self, x, y) != cell_state(other, x, y))
+            .collect())
+    }
+
+    /**
+     * Shannon entropy (in nats) of the spatial distribution of `species` over all nodes,
+     * 0 when there are no agents of `species`
+     */
+    pub fn distribution_entropy(&self, species: AgentSpecies) -> f32 {
+        let total: u32 = self
+            .nodes
+            .iter()
+            .map(|node| node.get_agents_with_species(&species))
+            .sum();
+
+        if total == 0 {
+            return 0.0;
+        }
+
+        -self
+            .nodes
+            .iter()
+            .map(|node| node.get_agents_with_species(&species))
+            .filter(|agents| *agents > 0)
+            .map(|agents| {
+                let p = agents as f32 / total as f32;
+                p * p.ln()
+            })
+            .sum::<f32>()
+    }
+
+    /**
+     * Kullback-Leibler divergence (in nats) of the spatial distribution of `species` from the
+     * uniform distribution over the N active nodes: Σ p_i ln(p_i N) = ln(N) -
+     * `distribution_entropy`.
+     * 0 for a uniform spread and ln(N) when all agents share a single node.
+     */
+    pub fn kl_divergence_from_uniform(&self, species: AgentSpecies) -> f32 {
+        if self
+            .nodes
+            .iter()
+            .all(|node| node.get_agents_with_species(&species) == 0)
+        {
+            return 0.0;
+        }
+        (self.nodes_iter().count() as f32).ln() - self.distribution_entropy(species)
+    }
+
+    /**
+     * Excess kurtosis of the `species` agent counts per active node, from the moments gathered
+     * in one pass: m4 / m2^2 - 3. Positive when most agents sit in a few nodes, about 1 / mean
+     * for agents scattered independently. NaN when all nodes hold the same number of agents.
+     */
+    pub fn agent_distribution_kurtosis(&self, species: AgentSpecies) -> f32 {
+        let (n, sums) = self
+            .nodes
+            .iter()
+            .filter(|node| node.active)
+            .map(|node| node.get_agents_with_species(&species) as f64)
+            .fold((0.0, [0.0; 4]), |(n, [s1, s2, s3, s4]), x| {
+                let x2 = x * x;
+                (n + 1.0, [s1 + x, s2 + x2, s3 + x2 * x, s4 + x2 * x2])
+            });
+        let [mean, raw2, raw3, raw4] = sums.map(|sum| sum / n);
+
+        let m2 = raw2 - mean * mean;
+        let m4 = raw4 - 4.0 * mean * raw3 + 6.0 * mean * mean * raw2 - 3.0 * mean.powi(4);
+        if m2 <= 0.0 {
+            return f32::NAN;
+        }
+        (m4 / (m2 * m2) - 3.0) as f32
+    }
+
+    /**
+     * The `qs` quantiles (0 to 1) of the `species` agent counts per active node, each the count
+     * at the nearest rank, so that 0.5 gives the median and 1 the maximum. 0 without nodes.
+     */
+    pub fn agent_quantiles(&self, species: AgentSpecies, qs: &[f32]) -> Vec<u32> {
+        let mut counts: Vec<u32> = self
+            .nodes
+            .iter()
+            .filter(|node| node.active)
+            .map(|node| node.get_agents_with_species(&species))
+            .collect();
+        counts.sort_unstable();
+
+        qs.iter()
+            .map(|q| {
+                let rank = (q.clamp(0.0, 1.0) * counts.len().saturating_sub(1) as f32).round();
+                counts.get(rank as usize).copied().unwrap_or(0)
+            })
+            .collect()
+    }
+
+    /**
+     * Cross-entropy H(p, q) = -Σ p_i ln(q_i) (in nats) of the current spatial distribution p of
+     * all agents over the grid cells against the distribution q in `reference`, e.g. a
+     * `snapshot` of the initial state. Cells that are empty in the reference count as holding
+     * `SNAPSHOT_EPSILON` of it. 0 when there are no agents, `UniverseError::SizeMismatch` if the
+     * reference has another size.
+     */
+    pub fn cross_entropy_from_snapshot(
+        &self,
+        reference: &UniverseState,
+    ) -> Result<f32, UniverseError> {
+        Ok(self
+            .snapshot_distributions(reference)?
+            .filter(|(p, _)| *p > 0.0)
+            .map(|(p, q)| -p * q.max(SNAPSHOT_EPSILON).ln())
+            .sum::<f64>() as f32)
+    }
+
+    /**
+     * Kullback-Leibler divergence Σ p_i ln(p_i / q_i) (in nats) of the current spatial
+     * distribution of all agents from the one in `reference`, the cross-entropy minus the
+     * entropy of p. 0 when the agents are spread as in the reference.
+     */
+    pub fn kl_divergence_from_snapshot(
+        &self,
+        reference: &UniverseState,
+    ) -> Result<f32, UniverseError> {
+        Ok(self
+            .snapshot_distributions(reference)?
+            .filter(|(p, _)| *p > 0.0)
+            .map(|(p, q)| p * (p / q.max(SNAPSHOT_EPSILON)).ln())
+            .sum::<f64>() as f32)
+    }
+
+    /**
+     * Per grid cell, the fraction of all agents on it now and in `reference`, paired by the
+     * cell of every node so that the node order of either side does not matter
+     */
+    fn snapshot_distributions(
+        &self,
+        reference: &UniverseState,
+    ) -> Result<impl Iterator<Item = (f64, f64)>, UniverseError> {
+        if reference.size != self.size {
+            return Err(UniverseError::SizeMismatch {
+                expected: self.size,
+                found: reference.size,
+            });
+        }
+
+        let cells = (self.size * self.size) as usize;
+        let mut current = vec![0u64; cells];
+        for (x, y, node) in self.nodes_iter() {
+            current[(y * self.size + x) as usize] = (node.red_agents + node.blue_agents) as u64;
+        }
+        let mut previous = vec![0u64; cells];
+        for (index, node) in reference.nodes.iter().enumerate() {
+            let Some(agents) = previous.get_mut(node.cell as usize) else {
+                return Err(UniverseError::InvalidCell {
+                    node: index,
+                    cell: node.cell,
+                });
+            };
+            *agents = (node.red_agents + node.blue_agents) as u64;
+        }
+
+        let current_total: u64 = current.iter().sum();
+        let previous_total: u64 = previous.iter().sum();
+        Ok(current.into_iter().zip(previous).map(move |(now, then)| {
+            (
+                now as f64 / current_total as f64,
+                then as f64 / previous_total as f64,
+            )
+        }))
+    }
+
+    /**
+     * Tick `iterations` times, recording the `distribution_entropy` of `species` after each tick
+     */
+    pub fn entropy_timeseries(&mut self, species: AgentSpecies, iterations: u32) -> Vec<f32> {
+        (0..iterations)
+            .map(|_| {
+                self.tick();
+                self.distribution_entropy(species)
+            })
+            .collect()
+    }
+
+    /**
+     * Number of red agents minus the number of blue agents, positive while red is ahead
+     */
+    pub fn advantage(&self) -> i64 {
+        self.nodes
+            .iter()
+            .map(|node| node.red_agents as i64 - node.blue_agents as i64)
+            .sum()
+    }
+
+    /**
+     * Tick `iterations` times, recording the `advantage` after each tick
+     */
+    pub fn advantage_timeseries(&mut self, iterations: u32) -> Vec<i64> {
+        (0..iterations)
+            .map(|_| {
+                self.tick();
+                self.advantage()
+            })
+            .collect()
+    }
+
+    /**
+     * Tick `ticks` times, recording the (red, blue) graffiti of the node at (x, y) after each
+     * tick, (0, 0) for a removed cell
+     */
+    pub fn node_graffiti_timeseries(&mut self, x: u32, y: u32, ticks: u32) -> Vec<(f32, f32)> {
+        (0..ticks)
+            .map(|_| {
+                self.tick();
+                match self.node_index(x, y) {
+                    Some(index) => (
+                        self.nodes[index].graffiti.red,
+                        self.nodes[index].graffiti.blue,
+                    ),
+                    None => (0.0, 0.0),
+                }
+            })
+            .collect()
+    }
+
+    /**
+     * Trajectory of the node at (x, y) in the (red graffiti, blue graffiti) plane over `ticks`
+     * ticks, for phase-space plots. The same points as `node_graffiti_timeseries`.
+     */
+    pub fn node_graffiti_phase_portrait(&mut self, x: u32, y: u32, ticks: u32) -> Vec<(f32, f32)> {
+        self.node_graffiti_timeseries(x, y, ticks)
+    }
+
+    /**
+     * `value` of the node in every grid cell (row-major), 0 for removed cells
+     */
+    fn cell_field(&self, value: impl Fn(&Node2D) -> f32) -> Vec<f32> {
+        (0..self.size * self.size)
+            .map(|cell| {
+                self.node_index(cell % self.size, cell / self.size)
+                    .map_or(0.0, |index| value(&self.nodes[index]))
+            })
+            .collect()
+    }
+
+    /**
+     * Per grid cell (row-major), the spread (max minus min) of the push strength of `species`
+     * over the neighbours of its node, 0 for removed cells. High values mark regions that
+     * strongly funnel agents.
+     */
+    pub fn push_gradient(&self, species: AgentSpecies) -> Vec<f32> {
+        self.cell_field(|node| {
+            let (min, max) = node.neighbours.into_iter().fold(
+                (f32::INFINITY, f32::NEG_INFINITY),
+                |(min, max), neighbour_idx| {
+                    let push = self.nodes[neighbour_idx as usize].get_push_strength(&species);
+                    (min.min(push), max.max(push))
+                },
+            );
+            max - min
+        })
+    }
+
+    /**
+     * Per grid cell (row-major), the discrete graph Laplacian of the graffiti of `species`: the
+     * summed difference sum_j (g_j - g_i) over the neighbours j of node i, 0 for removed cells.
+     * Negative at local maxima, positive at local minima, its magnitude measures the curvature
+     * of the field.
+     */
+    pub fn graffiti_laplacian(&self, species: AgentSpecies) -> Vec<f32> {
+        self.cell_field(|node| {
+            let graffiti = node.get_graffiti(&species);
+            node.neighbours
+                .into_iter()
+                .map(|neighbour_idx| {
+                    self.nodes[neighbour_idx as usize].get_graffiti(&species) - graffiti
+                })
+                .sum()
+        })
+    }
+
+    /**
+     * Per grid cell (row-major), the magnitude sqrt(dx^2 + dy^2) of the central-difference
+     * gradient of the graffiti of `species`, taken between the left/right and top/bottom
+     * neighbours, 0 for removed cells. Peaks along territory boundaries.
+     */
+    pub fn graffiti_gradient_magnitude(&self, species: AgentSpecies) -> Vec<f32> {
+        let graffiti_at = |index: u32| self.nodes[index as usize].get_graffiti(&species);
+
+        self.cell_field(|node| {
+            let neighbours = node.neighbours;
+            let dx = (graffiti_at(neighbours.right) - graffiti_at(neighbours.left)) / 2.0;
+            let dy = (graffiti_at(neighbours.bottom) - graffiti_at(neighbours.top)) / 2.0;
+            (dx * dx + dy * dy).sqrt()
+        })
+    }
+
+    /**
+     * Per grid cell (row-major), the fraction red / (red + blue) of its agents that are red.
+     * NaN for cells without agents, including walls and removed cells.
+     */
+    pub fn ratio_field(&self) -> Vec<f32> {
+        (0..self.size * self.size)
+            .map(|cell| self.ratio_at(cell % self.size, cell / self.size))
+            .collect()
+    }
+
+    /**
+     * Per grid cell (row-major), the species richness: how many species have at least one
+     * agent there. 0, 1 or 2 with two species; 0 for walls and removed cells.
+     */
+    pub fn richness_field(&self) -> Vec<u8> {
+        (0..self.size * self.size)
+            .map(|cell| {
+                let (red, blue) = self.agents_at(cell % self.size, cell / self.size);
+                (red > 0) as u8 + (blue > 0) as u8
+            })
+            .collect()
+    }
+
+    /**
+     * Central-difference gradient (d/dx, d/dy) of the `ratio_field` at (x, y), wrapping around
+     * the edges. It points across the species front, towards the red side. NaN when one of
+     * the four neighbouring cells has no agents.
+     */
+    pub fn ratio_gradient(&self, x: u32, y: u32) -> (f32, f32) {
+        let size = self.size;
+        let (left, right) = ((x + size - 1) % size, (x + 1) % size);
+        let (up, down) = ((y + size - 1) % size, (y + 1) % size);
+
+        (
+            (self.ratio_at(right, y) - self.ratio_at(left, y)) / 2.0,
+            (self.ratio_at(x, down) - self.ratio_at(x, up)) / 2.0,
+        )
+    }
+
+    /**
+     * Fraction of the agents at (x, y) that are red, NaN without agents
+     */
+    fn ratio_at(&self, x: u32, y: u32) -> f32 {
+        let (red, blue) = self.agents_at(x, y);
+        match red + blue {
+            0 => f32::NAN,
+            agents => red as f32 / agents as f32,
+        }
+    }
+
+    /**
+     * The domains of `species`: connected groups of nodes whose graffiti it dominates (see
+     * `Node2D::dominant_species`), as lists of grid cells (y * size + x). The clusters are
+     * ordered by their first cell in row-major order. Neighbours are the four grid neighbours,
+     * wrapping around the edges.
+     */
+    pub fn species_clusters(&self, species: AgentSpecies) -> Vec<Vec<u32>> {
+        let in_domain = |node: &Node2D| node.active && node.dominant_species() == Some(species);
+        let mut visited = vec![false; self.nodes.len()];
+        let mut clusters = Vec::new();
+
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let Some(start) = self.node_index(x, y) else {
+                    continue;
+                };
+                if visited[start] || !in_domain(&self.nodes[start]) {
+                    continue;
+                }
+
+                visited[start] = true;
+                let mut cluster = Vec::new();
+                let mut queue = VecDeque::from([start]);
+                while let Some(index) = queue.pop_front() {
+                    cluster.push(self.nodes[index].cell);
+                    for neighbour_idx in self.nodes[index].neighbours {
+                        let neighbour_idx = neighbour_idx as usize;
+                        if !visited[neighbour_idx] && in_domain(&self.nodes[neighbour_idx]) {
+                            visited[neighbour_idx] = true;
+                            queue.push_back(neighbour_idx);
+                        }
+                    }
+                }
+                clusters.push(cluster);
+            }
+        }
+
+        clusters
+    }
+
+    /**
+     * Tick `iterations` times, recording the number of `species_clusters` of `species` after
+     * each tick. Coarsening shows up as a falling count while domains merge.
+     */
+    pub fn domain_count_timeseries(&mut self, species: AgentSpecies, iterations: u32) -> Vec<u32> {
+        (0..iterations)
+            .map(|_| {
+                self.tick();
+                self.species_clusters(species).len() as u32
+            })
+            .collect()
+    }
+
+    /**
+     * Degree of spatial separation of the species, between 0 (well mixed) and 1 (segregated).
+     * For every 2x2 block of nodes the imbalance |red - blue| is summed and divided by the total
+     * number of agents in all blocks. A checkerboard of pure nodes therefore counts as mixed.
+     */
+    pub fn segregation_index(&self) -> f32 {
+        let (imbalance, agents) =
+            self.nodes
+                .iter()
+                .fold((0u64, 0u64), |(imbalance, agents), node| {
+                    let right = &self.nodes[node.neighbours.right as usize];
+                    let bottom = &self.nodes[node.neighbours.bottom as usize];
+                    let bottom_right = &self.nodes[right.neighbours.bottom as usize];
+                    let block = [node, right, bottom, bottom_right];
+
+                    let red: u64 = block.iter().map(|node| node.red_agents as u64).sum();
+                    let blue: u64 = block.iter().map(|node| node.blue_agents as u64).sum();
+
+                    (imbalance + red.abs_diff(blue), agents + red + blue)
+                });
+
+        if agents == 0 {
+            return 0.0;
+        }
+        imbalance as f32 / agents as f32
+    }
+
+    /**
+     * Number of edges in the neighbour graph joining a red-dominant and a blue-dominant node.
+     * Ranges from 0 to the total number of edges (`2 * size^2` on the torus, for a checkerboard).
+     */
+    pub fn species_boundary_length(&self) -> u32 {
+        self.nodes
+            .iter()
+            .map(|node| {
+                let species = node.dominant_species();
+                [node.neighbours.right, node.neighbours.bottom]
+                    .iter()
+                    .filter(|neighbour_idx| {
+                        let neighbour_species =
+                            self.nodes[**neighbour_idx as usize].dominant_species();
+                        species.is_some()
+                            && neighbour_species.is_some()
+                            && species != neighbour_species
+                    })
+                    .count() as u32
+            })
+            .sum()
+    }
+
+    /**
+     * Box-counting dimension of the interface between red-dominant and blue-dominant nodes:
+     * the slope of log N(s) against log(1 / s), with N(s) the number of s x s boxes holding
+     * interface nodes, for box sizes s = 1, 2, 4, ... up to `size / 4`.
+     * Near 1 for a smooth boundary and up to 2 for a space-filling one. Only meaningful for
+     * large grids (`size` of 64 or more gives five box sizes); returns 0 when `size` is below 8
+     * or there is no interface.
+     */
+    pub fn interface_box_dimension(&self) -> f32 {
+        let interface: Vec<(u32, u32)> = self
+            .nodes_iter()
+            .filter(|(_, _, node)| {
+                let species = node.dominant_species();
+                species.is_some()
+                    && [node.neighbours.right, node.neighbours.bottom]
+                        .iter()
+                        .any(|neighbour_idx| {
+                            let neighbour_species =
+                                self.nodes[*neighbour_idx as usize].dominant_species();
+                            neighbour_species.is_some() && neighbour_species != species
+                        })
+            })
+            .map(|(x, y, _)| (x, y))
+            .collect();
+
+        let box_sizes: Vec<u32> = (0..)
+            .map(|exponent| 1 << exponent)
+            .take_while(|box_size| box_size * 4 <= self.size)
+            .collect();
+        if interface.is_empty() || box_sizes.len() < 2 {
+            return 0.0;
+        }
+
+        // Least squares fit of log N(s) = D log(1 / s) + c
+        let points: Vec<(f32, f32)> = box_sizes
+            .iter()
+            .map(|box_size| {
+                let boxes: HashSet<(u32, u32)> = interface
+                    .iter()
+                    .map(|(x, y)| (x / box_size, y / box_size))
+                    .collect();
+                (-(*box_size as f32).ln(), (boxes.len() as f32).ln())
+            })
+            .collect();
+        let n = points.len() as f32;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f32>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f32>() / n;
+        let covariance: f32 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let variance: f32 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+
+        covariance / variance
+    }
+
+    /**
+     * Euler characteristic χ = V - E + F of the territory of `species`: the nodes it dominates,
+     * the edges between two of them and the 2x2 squares made of four of them, including those
+     * wrapping around the torus. This is 1 for a territory shaped like a disc and 0 for one
+     * with a single hole, for a band around the torus and for the whole torus. Because the grid
+     * wraps, half of the universe is such a band, so it has χ = 0 rather than the 1 of a half
+     * plane; a quadrant is a disc.
+     */
+    pub fn euler_characteristic(&self, species: AgentSpecies) -> i32 {
+        let mut territory = vec![false; (self.size * self.size) as usize];
+        for (x, y, node) in self.nodes_iter() {
+            territory[(y * self.size + x) as usize] = node.dominant_species() == Some(species);
+        }
+        let in_territory =
+            |x: u32, y: u32| territory[((y % self.size) * self.size + x % self.size) as usize];
+
+        let (mut vertices, mut edges, mut faces) = (0, 0, 0);
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if !in_territory(x, y) {
+                    continue;
+                }
+                vertices += 1;
+                edges += in_territory(x + 1, y) as i32 + in_territory(x, y + 1) as i32;
+                if in_territory(x + 1, y) && in_territory(x, y + 1) && in_territory(x + 1, y + 1) {
+                    faces += 1;
+                }
+            }
+        }
+
+        vertices - edges + faces
+    }
+
+    /**
+     * Centre of mass (x, y) of the agents of `species`, taken as a circular mean along both
+     * axes of the torus. `None` if there are no such agents.
+     */
+    pub fn agent_centroid(&self, species: AgentSpecies) -> Option<(f32, f32)> {
+        self.centroid(|node| node.get_agents_with_species(&species) as f32)
+    }
+
+    /**
+     * Graffiti weighted centre (x, y) of `species`, taken as a circular mean along both axes of
+     * the torus. `None` if there is no graffiti of `species`. Graffiti lags behind the agents
+     * that deposit it, so this trails `agent_centroid` for a moving population.
+     */
+    pub fn graffiti_centroid(&self, species: AgentSpecies) -> Option<(f32, f32)> {
+        self.centroid(|node| node.get_graffiti(&species))
+    }
+
+    /**
+     * Tick `iterations` times and return the mean displacement per tick (x, y) of the
+     * `agent_centroid` of `species`. Each step is taken the short way around the torus, so this
+     * measures directed transport as long as the centroid moves less than half the grid per
+     * tick. (0, 0) while there are no agents of `species`.
+     */
+    pub fn drift_velocity(&mut self, species: AgentSpecies, iterations: u32) -> (f32, f32) {
+        let size = self.size as f32;
+        let wrap = |delta: f32| (delta + size / 2.0).rem_euclid(size) - size / 2.0;
+
+        let mut displacement = (0.0, 0.0);
+        let mut previous = self.agent_centroid(species);
+        for _ in 0..iterations {
+            self.tick();
+            let current = self.agent_centroid(species);
+            if let (Some((x0, y0)), Some((x1, y1))) = (previous, current) {
+                displacement.0 += wrap(x1 - x0);
+                displacement.1 += wrap(y1 - y0);
+            }
+            previous = current;
+        }
+
+        if iterations == 0 {
+            return (0.0, 0.0);
+        }
+        (
+            displacement.0 / iterations as f32,
+            displacement.1 / iterations as f32,
+        )
+    }
+
+    fn centroid(&self, weight: impl Fn(&Node2D) -> f32) -> Option<(f32, f32)> {
+        let size = self.size as f32;
+        let x = circular_mean(
+            self.nodes_iter()
+                .map(|(x, _, node)| (x as f32, weight(node))),
+            size,
+        )?;
+        let y = circular_mean(
+            self.nodes_iter()
+                .map(|(_, y, node)| (y as f32, weight(node))),
+            size,
+        )?;
+        Some((x, y))
+    }
+
+    /**
+     * Number of agents of `species` on nodes dominated by the graffiti of the other species
+     */
+    pub fn territory_incursion(&self, species: AgentSpecies) -> u32 {
+        let other_species = match species {
+            AgentSpecies::Red => AgentSpecies::Blue,
+            AgentSpecies::Blue => AgentSpecies::Red,
+        };
+
+        self.nodes
+            .iter()
+            .filter(|node| node.dominant_species() == Some(other_species))
+            .map(|node| node.get_agents_with_species(&species))
+            .sum()
+    }
+
+    /**
+     * How much the species avoid sharing nodes: 1 minus the observed co-occupation
+     * Σ red_i · blue_i divided by its expectation total_red · total_blue / N for agents placed
+     * independently on the N active nodes. Near 1 for strong exclusion, near 0 for random
+     * co-occupation and 0 when either species is absent.
+     */
+    pub fn mutual_exclusion_index(&self) -> f32 {
+        let (co_occupation, total_red, total_blue) = self.nodes.iter().fold(
+            (0.0, 0.0, 0.0),
+            |(co_occupation, total_red, total_blue), node| {
+                (
+                    co_occupation + node.red_agents as f64 * node.blue_agents as f64,
+                    total_red + node.red_agents as f64,
+                    total_blue + node.blue_agents as f64,
+                )
+            },
+        );
+
+        let expected_co_occupation = total_red * total_blue / self.nodes_iter().count() as f64;
+        if expected_co_occupation == 0.0 {
+            return 0.0;
+        }
+        (1.0 - co_occupation / expected_co_occupation) as f32
+    }
+
+    /**
+     * Co-location of the species: Σ min(red_i, blue_i) over the nodes, divided by the total
+     * number of agents. 0 when no node holds both species, 0.5 when equal populations share
+     * every node in equal numbers, and 0 for an empty universe.
+     */
+    pub fn colocation_index(&self) -> f32 {
+        let (overlap, total) = self
+            .nodes
+            .iter()
+            .fold((0u64, 0u64), |(overlap, total), node| {
+                (
+                    overlap + node.red_agents.min(node.blue_agents) as u64,
+                    total + (node.red_agents + node.blue_agents) as u64,
+                )
+            });
+
+        if total == 0 {
+            return 0.0;
+        }
+        overlap as f32 / total as f32
+    }
+
+    /**
+     * Winding number of the graffiti contrast of `species` (its graffiti minus that of the other
+     * species) along the closed horizontal `row`: half the number of sign changes, which on the
+     * torus is always even. This equals the number of separate stripes of `species` crossed by
+     * the row. Nodes where the contrast is exactly 0 are skipped.
+     */
+    pub fn winding_number_x(&self, species: AgentSpecies, row: u32) -> i32 {
+        let signs: Vec<bool> = (0..self.size)
+            .filter_map(|x| {
+                let node = &self.nodes[self.node_index(x, row)?];
+                let contrast = node.graffiti.red - node.graffiti.blue;
+                let contrast = match species {
+                    AgentSpecies::Red => contrast,
+                    AgentSpecies::Blue => -contrast,
+                };
+                (contrast != 0.0).then_some(contrast > 0.0)
+            })
+            .collect();
+
+        let sign_changes = signs
+            .iter()
+            .zip(signs.iter().cycle().skip(1))
+            .filter(|(sign, next_sign)| sign != next_sign)
+            .count();
+        (sign_changes / 2) as i32
+    }
+
+    /**
+     * Agent flows of the most recent tick, both species together: for every grid cell,
+     * row-major (y * size + x), the `(destination cell, count)` pairs of the cells its agents
+     * moved to, in the order top, right, bottom, left. Agents that bounced off a wall have the
+     * cell itself as destination, so the counts of a row add up to the agents that were on the
+     * cell and those towards a cell to the agents now on it. Destinations without agents are
+     * left out; the rows of walls and removed cells are empty.
+     */
+    pub fn compute_flow_matrix(&self) -> Vec<Vec<(u32, u32)>> {
+        self.flow_matrix(|node| {
+            let agents_out = node.agents_out[0].into_iter().zip(node.agents_out[1]);
+            agents_out.map(|(red, blue)| red + blue).collect()
+        })
+    }
+
+    /**
+     * Like `compute_flow_matrix`, counting only the agents of `species`
+     */
+    pub fn compute_flow_matrix_for_species(&self, species: AgentSpecies) -> Vec<Vec<(u32, u32)>> {
+        let species_index = match species {
+            AgentSpecies::Red => 0,
+            AgentSpecies::Blue => 1,
+        };
+        self.flow_matrix(|node| node.agents_out[species_index].into_iter().collect())
+    }
+
+    /**
+     * All non-zero flows of the most recent tick, per species, for drawing flow arrows. Agents
+     * that bounced off a wall show up as a flow from a node to itself.
+     */
+    pub fn agents_out_iter(&self) -> impl Iterator<Item = AgentFlow> + '_ {
+        [AgentSpecies::Red, AgentSpecies::Blue]
+            .into_iter()
+            .flat_map(move |species| {
+                self.compute_flow_matrix_for_species(species)
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(move |(from_cell, row)| {
+                        row.into_iter().map(move |(to_cell, count)| AgentFlow {
+                            from_cell: from_cell as u32,
+                            to_cell,
+                            species,
+                            count,
+                        })
+                    })
+            })
+    }
+
+    /**
+     * Sum of the counts of `agents_out_iter`: all agents on the grid at the start of the last tick
+     */
+    pub fn total_flow(&self) -> u32 {
+        self.agents_out_iter().map(|flow| flow.count).sum()
+    }
+
+    /**
+     * Whether the agent flows of the most recent tick are in detailed balance: for every edge
+     * and species, the agents moving one way and those moving back differ by at most `epsilon`
+     * times the traffic over the edge. Bounces off walls are ignored. True before the first
+     * tick, when nothing has moved.
+     */
+    pub fn is_at_equilibrium(&self, epsilon: f32) -> bool {
+        // agents of species index `species` that moved from node `from` to node `to`
+        let flow = |species: usize, from: &Node2D, to: u32| -> u32 {
+            from.neighbours
+                .into_iter()
+                .zip(from.agents_out[species])
+                .filter(|(neighbour_idx, _)| *neighbour_idx == to)
+                .map(|(_, count)| count)
+                .sum()
+        };
+
+        self.nodes.iter().all(|node| {
+            node.neighbours
+                .into_iter()
+                .filter(|neighbour_idx| *neighbour_idx != node.index)
+                .all(|neighbour_idx| {
+                    let neighbour = &self.nodes[neighbour_idx as usize];
+                    (0..2).all(|species| {
+                        let forward = flow(species, node, neighbour_idx) as f32;
+                        let backward = flow(species, neighbour, node.index) as f32;
+                        (forward - backward).abs() <= epsilon * (forward + backward)
+                    })
+                })
+        })
+    }
+
+    fn flow_matrix(&self, agents_out: impl Fn(&Node2D) -> Vec<u32>) -> Vec<Vec<(u32, u32)>> {
+        let mut matrix = vec![Vec::new(); (self.size * self.size) as usize];
+        for node in &self.nodes {
+            // merge directions leading to the same cell, e.g. into a wall
+            let mut edges: Vec<(u32, u32)> = Vec::with_capacity(4);
+            for (neighbour_idx, count) in node.neighbours.into_iter().zip(agents_out(node)) {
+                let destination = self.node_cell(neighbour_idx as usize);
+                match edges.iter_mut().find(|(other, _)| *other == destination) {
+                    Some((_, total)) => *total += count,
+                    None => edges.push((destination, count)),
+                }
+            }
+            edges.retain(|(_, count)| *count > 0);
+            matrix[node.cell as usize] = edges;
+        }
+        matrix
+    }
+
+    /**
+     * Net number of agents of `species` that crossed from column x - 1 to column x (wrapping
+     * around for x = 0) in the most recent tick: those moving right minus those moving left.
+     * Agents bouncing off a wall do not cross.
+     */
+    pub fn flux_through_column(&self, x: u32, species: AgentSpecies) -> i32 {
+        let species_index = match species {
+            AgentSpecies::Red => 0,
+            AgentSpecies::Blue => 1,
+        };
+        let left_column = (x + self.size - 1) % self.size;
+
+        self.nodes_iter()
+            .map(|(node_x, _, node)| {
+                let agents_out = &node.agents_out[species_index];
+                if node_x == left_column && node.neighbours.right != node.index {
+                    agents_out.right as i32
+                } else if node_x == x && node.neighbours.left != node.index {
+                    -(agents_out.left as i32)
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    /**
+     * Shannon entropy (in nats) of the agent flow over the edges in the most recent tick,
+     * with p_e the fraction of all moving agents (of both species) that used edge e.
+     * Low values mean the agents converge on a few paths, 0 before the first tick.
+     */
+    pub fn flow_entropy(&self) -> f32 {
+        let flows: Vec<u32> = self
+            .compute_flow_matrix()
+            .into_iter()
+            .flatten()
+            .map(|(_, count)| count)
+            .collect();
+
+        let total: u32 = flows.iter().sum();
+        if total == 0 {
+            return 0.0;
+        }
+
+        -flows
+            .iter()
+            .map(|count| {
+                let p = *count as f32 / total as f32;
+                p * p.ln()
+            })
+            .sum::<f32>()
+    }
+
+    /**
+     * Entropy production of the last tick, σ = Σ_{i→j} J_ij ln(J_ij / J_ji) with J_ij the
+     * number of agents of a species that moved from cell i to cell j, summed over both species.
+     * Each pair of directions contributes (J_ij - J_ji) ln(J_ij / J_ji) >= 0, so σ is 0 when
+     * the flows balance (detailed balance) and grows with net currents. A pair crossed in only
+     * one direction would contribute without bound, so `FLUX_PSEUDOCOUNT` agents are added to
+     * both directions of every crossed pair; self-loops into walls carry no current.
+     */
+    pub fn entropy_production_rate(&self) -> f32 {
+        [AgentSpecies::Red, AgentSpecies::Blue]
+            .into_iter()
+            .map(|species| {
+                // (flux from the lower to the higher cell, flux back) per crossed pair
+                let mut pairs: HashMap<(u32, u32), (u32, u32)> = HashMap::new();
+                for (source, row) in self
+                    .compute_flow_matrix_for_species(species)
+                    .into_iter()
+                    .enumerate()
+                {
+                    let source = source as u32;
+                    for (destination, count) in row {
+                        if destination > source {
+                            pairs.entry((source, destination)).or_default().0 += count;
+                        } else if destination < source {
+                            pairs.entry((destination, source)).or_default().1 += count;
+                        }
+                    }
+                }
+
+                pairs
+                    .values()
+                    .map(|(forward, backward)| {
+                        let (forward, backward) = (*forward as f64, *backward as f64);
+                        (forward - backward)
+                            * ((forward + FLUX_PSEUDOCOUNT) / (backward + FLUX_PSEUDOCOUNT)).ln()
+                    })
+                    .sum::<f64>()
+            })
+            .sum::<f64>() as f32
+    }
+
+    /**
+     * Mean repulsion felt per agent: beta times the graffiti of the other species on its node
+     */
+    pub fn energy(&self) -> f32 {
+        let (repulsion, agents) = self
+            .nodes
+            .iter()
+            .fold((0.0, 0), |(repulsion, agents), node| {
+                (
+                    repulsion
+                        + node.red_agents as f32 * node.graffiti.blue
+                        + node.blue_agents as f32 * node.graffiti.red,
+                    agents + node.red_agents + node.blue_agents,
+                )
+            });
+
+        if agents == 0 {
+            return 0.0;
+        }
+        self.hyper_params.beta * repulsion / agents as f32
+    }
+
+    /**
+     * Add `delta` agents of `species` to the node at (x, y) and tick `response_ticks` times,
+     * recording after each tick the excess segregation index over an unperturbed copy
+     */
+    pub fn step_response(
+        &mut self,
+        species: AgentSpecies,
+        x: u32,
+        y: u32,
+        delta: u32,
+        response_ticks: u32,
+    ) -> Vec<f32> {
+        let mut control = self.clone();
+        let index = self
+            .node_index(x, y)
+            .unwrap_or_else(|| panic!("no node at ({}, {})", x, y));
+        self.nodes[index].add_agents(delta, species);
+
+        (0..response_ticks)
+            .map(|_| {
+                self.tick();
+                control.tick();
+                self.segregation_index() - control.segregation_index()
+            })
+            .collect()
+    }
+
+    /**
+     * Tick until some node holds agents of both species, for at most `max_iters` ticks.
+     * Returns the iteration of first contact, `None` if the species never met.
+     */
+    pub fn time_to_contact(&mut self, max_iters: u32) -> Option<u32> {
+        let in_contact = |universe: &Universe2D| {
+            universe
+                .nodes
+                .iter()
+                .any(|node| node.red_agents > 0 && node.blue_agents > 0)
+        };
+
+        for _ in 0..max_iters {
+            if in_contact(self) {
+                return Some(self.iteration);
+            }
+            self.tick();
+        }
+        in_contact(self).then_some(self.iteration)
+    }
+
+    /**
+     * Tick `n` times and return the lag-1 autocorrelation of the `segregation_index` series,
+     * cov(x[t], x[t + 1]) / var(x). Near 1 when the state changes slowly, near 0 when it
+     * decorrelates from one tick to the next. NaN for fewer than 3 ticks or a constant index.
+     */
+    pub fn inter_tick_correlation(&mut self, n: u32) -> f32 {
+        let mut variance = Welford::new();
+        let mut lagged = WelfordCovariance::new();
+        let mut previous = None;
+        for _ in 0..n {
+            self.tick();
+            let segregation_index = self.segregation_index();
+            variance.push(segregation_index);
+            if let Some(previous) = previous {
+                lagged.push(previous, segregation_index);
+            }
+            previous = Some(segregation_index);
+        }
+
+        if n < 3 {
+            return f32::NAN;
+        }
+        lagged.covariance() / variance.variance()
+    }
+
+    /**
+     * Simulated annealing: tick `total_ticks` times while beta decreases geometrically from
+     * `initial_beta` on the first tick to `final_beta` on the last, returning the stats after
+     * every tick. Lowering the avoidance lets the agents leave locally segregated states.
+     * Beta stays at `final_beta` afterwards; a hyper parameter schedule takes precedence.
+     *
+     * # Panics
+     * If either beta is not above 0
+     */
+    pub fn run_with_annealing(
+        &mut self,
+        initial_beta: f32,
+        final_beta: f32,
+        total_ticks: u32,
+    ) -> Vec<TickStats> {
+        assert!(
+            initial_beta > 0.0 && final_beta > 0.0,
+            "annealing needs positive betas"
+        );
+
+        let ratio = final_beta / initial_beta;
+        let steps = total_ticks.saturating_sub(1).max(1) as f32;
+        (0..total_ticks)
+            .map(|tick| {
+                self.hyper_params.beta = initial_beta * ratio.powf(tick as f32 / steps);
+                self.tick();
+                self.tick_stats()
+            })
+            .collect()
+    }
+
+    /**
+     * Run `warmup` ticks that are discarded, then `measurement` ticks whose stats are
+     * accumulated into their mean and standard deviation
+     */
+    pub fn simulate_to_equilibrium_and_measure(
+        &mut self,
+        warmup: u32,
+        measurement: u32,
+    ) -> MeasurementResult {
+        self.iterate(warmup);
+
+        let mut segregation_index = Welford::new();
+        let mut energy = Welford::new();
+        for _ in 0..measurement {
+            let (stats, _) = self.tick_with_measurement(|_| ());
+            segregation_index.push(stats.segregation_index);
+            energy.push(stats.energy);
+        }
+
+        MeasurementResult {
+            samples: measurement,
+            mean_segregation_index: segregation_index.mean(),
+            std_segregation_index: segregation_index.std(),
+            mean_energy: energy.mean(),
+            std_energy: energy.std(),
+        }
+    }
+
+    /**
+     * For every value in `values`, run a copy of this universe for `ticks` ticks with `param`
+     * set to that value and return the final stats, in the order of `values`.
+     * The runs are done in parallel.
+     */
+    pub fn sensitivity_analysis(
+        &self,
+        param: SensitivityParam,
+        values: &[f32],
+        ticks: u32,
+    ) -> Vec<TickStats> {
+        values
+            .par_iter()
+            .map(|value| {
+                let mut universe = self.clone();
+                universe.set_hyper_params(self.hyper_params.with_param(param, *value));
+                universe.iterate(ticks);
+                universe.tick_stats()
+            })
+            .collect()
+    }
+
+    /**
+     * Robustness to node failures: the mean, over 10 trials, of the `segregation_index` after
+     * 100 ticks with a random `fraction` of the active nodes turned into walls, relative to the
+     * index after 100 ticks without removal. Near 1 when the territories hardly notice the
+     * barriers. The trials are run in parallel; NaN if the baseline index is 0.
+     */
+    pub fn node_removal_resilience(&self, fraction: f32, seed: u64) -> f32 {
+        const TRIALS: u32 = 10;
+        const TICKS: u32 = 100;
+
+        let segregation_after_ticks = |mut universe: Universe2D| {
+            universe.iterate(TICKS);
+            universe.segregation_index()
+        };
+        let baseline = segregation_after_ticks(self.clone());
+
+        // row-major cells of the active nodes, walls and removed cells stay masked out
+        let mut active: Vec<u32> = self
+            .nodes_iter()
+            .map(|(x, y, _)| y * self.size + x)
+            .collect();
+        active.sort_unstable();
+        let mut active_mask = vec![false; (self.size * self.size) as usize];
+        for cell in &active {
+            active_mask[*cell as usize] = true;
+        }
+        let removed =
+            ((fraction.clamp(0.0, 1.0) * active.len() as f32).round() as usize).min(active.len());
+        let mut prng = Rand32::new(seed);
+        let masks: Vec<Vec<bool>> = (0..TRIALS)
+            .map(|_| {
+                // partial Fisher-Yates shuffle, the first `removed` cells become walls
+                let mut candidates = active.clone();
+                let mut mask = active_mask.clone();
+                for i in 0..removed {
+                    let j = prng.rand_range(i as u32..candidates.len() as u32) as usize;
+                    candidates.swap(i, j);
+                    mask[candidates[i] as usize] = false;
+                }
+                mask
+            })
+            .collect();
+
+        let ratio_sum: f32 = masks
+            .par_iter()
+            .map(|mask| {
+                let mut universe = self.clone();
+                universe.apply_mask(mask).unwrap();
+                segregation_after_ticks(universe) / baseline
+            })
+            .sum();
+        ratio_sum / TRIALS as f32
+    }
+
+    /**
+     * Run one universe per seed in `seeds`, each with `agent_size` agents of both species placed
+     * at random from its seed, for `ticks` ticks, and summarise the distribution of the final
+     * `segregation_index`. The runs are spread over the rayon pool, each ticking serially.
+     */
+    pub fn run_ensemble(
+        size: u32,
+        agent_size: u32,
+        seeds: &[u64],
+        ticks: u32,
+        hyper_params: HyperParams,
+    ) -> EnsembleStats {
+        let mut segregation: Vec<f32> = seeds
+            .par_iter()
+            .map(|&seed| {
+                let mut universe = Universe2D::new_seeded(size, agent_size, seed);
+                universe.set_hyper_params(hyper_params);
+                for _ in 0..ticks {
+                    universe.tick_with(ComputationType::Serial);
+                }
+                universe.segregation_index()
+            })
+            .collect();
+        segregation.sort_by(f32::total_cmp);
+
+        let mut welford = Welford::new();
+        segregation.iter().for_each(|value| welford.push(*value));
+        EnsembleStats {
+            runs: seeds.len() as u32,
+            mean_segregation: welford.mean(),
+            std_segregation: welford.std(),
+            min_segregation: segregation.first().copied().unwrap_or(f32::NAN),
+            max_segregation: segregation.last().copied().unwrap_or(f32::NAN),
+            p10: quantile(&segregation, 0.1),
+            p50: quantile(&segregation, 0.5),
+            p90: quantile(&segregation, 0.9),
+        }
+    }
+
+    /**
+     * Run `trials` universes with `agents` agents of both species, placed at random from the
+     * trial number, with births and deaths for `iterations` ticks, and count how often each
+     * species ends with more agents than the other. Both rates are `WIN_BIRTH_DEATH_RATE` and
+     * the capacity of a node is the initial number of agents per node, rounded up, so the
+     * totals drift until a species takes over. Returns the (red, blue) fractions of the trials
+     * won; draws count for neither. The trials are spread over the rayon pool, each ticking
+     * serially.
+     */
+    pub fn win_probability(
+        size: u32,
+        agents: u32,
+        hyper_params: HyperParams,
+        trials: u32,
+        iterations: u32,
+    ) -> (f32, f32) {
+        let capacity = (2 * agents).div_ceil(size * size).max(1);
+        let (red_wins, blue_wins) = (0..trials)
+            .into_par_iter()
+            .map(|trial| {
+                let mut universe = Universe2D::new_seeded(size, agents, trial as u64);
+                universe.set_hyper_params(hyper_params);
+                universe.enable_birth_death(
+                    WIN_BIRTH_DEATH_RATE,
+                    WIN_BIRTH_DEATH_RATE,
+                    capacity,
+                    trial as u64,
+                );
+                for _ in 0..iterations {
+                    universe.tick_with(ComputationType::Serial);
+                }
+
+                let (red, blue) = universe
+                    .nodes_iter()
+                    .fold((0, 0), |(red, blue), (_, _, node)| {
+                        (red + node.red_agents, blue + node.blue_agents)
+                    });
+                ((red > blue) as u32, (blue > red) as u32)
+            })
+            .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+
+        (
+            red_wins as f32 / trials as f32,
+            blue_wins as f32 / trials as f32,
+        )
+    }
+
+    /**
+     * Finite-size scaling: for every grid size in `sizes`, run a random universe of that size
+     * with `agents_per_node` agents of each species per node for `ticks` ticks and measure the
+     * final `segregation_index`. Returns (size, segregation index) in the order of `sizes`.
+     * The sizes are run in parallel.
+     */
+    pub fn finite_size_scaling(
+        sizes: &[u32],
+        agents_per_node: u32,
+        ticks: u32,
+        hyper_params: HyperParams,
+    ) -> Vec<(u32, f32)> {
+        sizes
+            .par_iter()
+            .map(|&size| {
+                let mut universe = Universe2D::new(size, size * size * agents_per_node);
+                universe.set_hyper_params(hyper_params);
+                universe.iterate(ticks);
+                (size, universe.segregation_index())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_analysis {
+    use crate::{
+        agent_species::AgentSpecies,
+        hyper_params::{HyperParams, SensitivityParam},
+        movement_rule::{DirectionWeighted, MovementRule, Rand32},
+        nodes::Node,
+        universe::{Universe, Universe2D, UniverseError},
+    };
+
+    #[test]
+    fn test_hot_spots_near_seed() {
+        let mut universe = Universe2D::new(16, 0);
+        for y in 4..6 {
+            for x in 4..6 {
+                universe.nodes[(y * 16 + x) as usize].add_agents(250, AgentSpecies::Red);
+            }
+        }
+
+        universe.iterate(10);
+
+        let hot_spots = universe.hot_spots(5.0, AgentSpecies::Red);
+        assert!(!hot_spots.is_empty());
+        for (x, y) in &hot_spots {
+            let dx = x.abs_diff(4).min(16 - x.abs_diff(4));
+            let dy = y.abs_diff(4).min(16 - y.abs_diff(4));
+            assert!(dx <= 5 && dy <= 5, "hot spot ({}, {}) far from seed", x, y);
+        }
+
+        let hottest = universe
+            .nodes_iter()
+            .fold(0.0, |max: f32, (_, _, node)| max.max(node.graffiti.red));
+        let (x, y) = hot_spots[0];
+        assert_eq!(universe.nodes[(y * 16 + x) as usize].graffiti.red, hottest);
+
+        let cold_spots = universe.cold_spots(5.0, AgentSpecies::Red);
+        assert_eq!(hot_spots.len() + cold_spots.len(), 256);
+        assert!(universe.hot_spots(0.0, AgentSpecies::Blue).is_empty());
+    }
+
+    #[test]
+    fn test_diff_after_tick() {
+        let before = Universe2D::new(8, 200);
+        let mut after = before.clone();
+        after.tick();
+
+        assert_eq!(before.diff(&before), Ok(vec![]));
+
+        let changed = before.diff(&after).unwrap();
+        assert!(!changed.is_empty());
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let index = (y * 8 + x) as usize;
+                let (old, new) = (&before.nodes[index], &after.nodes[index]);
+                let is_changed = old.red_agents != new.red_agents
+                    || old.blue_agents != new.blue_agents
+                    || old.dominant_species() != new.dominant_species();
+
+                assert_eq!(changed.contains(&(x, y)), is_changed, "cell ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_diff_with_walls() {
+        let mut universe = Universe2D::new(4, 50);
+        let mut active = vec![true; 16];
+        active[0] = false;
+        universe.apply_mask(&active).unwrap();
+        assert_eq!(universe.diff(&universe.clone()), Ok(vec![]));
+
+        let mut compacted = universe.clone();
+        compacted.compact();
+        assert_eq!(universe.diff(&compacted), Ok(vec![]));
+
+        compacted.nodes[0].add_agents(1, AgentSpecies::Red); // cell (1, 0)
+        assert_eq!(universe.diff(&compacted), Ok(vec![(1, 0)]));
+    }
+
+    #[test]
+    fn test_diff_size_mismatch() {
+        let small = Universe2D::new(4, 10);
+        let large = Universe2D::new(5, 10);
+
+        assert_eq!(
+            small.diff(&large),
+            Err(UniverseError::SizeMismatch {
+                expected: 4,
+                found: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_entropy_timeseries() {
+        let mut universe = Universe2D::new(16, 0);
+        universe.nodes[0].add_agents(2000, AgentSpecies::Red);
+        assert_eq!(universe.distribution_entropy(AgentSpecies::Red), 0.0);
+        assert_eq!(universe.distribution_entropy(AgentSpecies::Blue), 0.0);
+
+        let timeseries = universe.entropy_timeseries(AgentSpecies::Red, 20);
+
+        assert_eq!(timeseries.len(), 20);
+        assert!(timeseries.windows(2).all(|pair| pair[1] >= pair[0]));
+        assert!(timeseries[19] <= (256.0f32).ln());
+    }
+
+    #[test]
+    fn test_advantage() {
+        let mut symmetric = Universe2D::new(6, 40);
+        assert_eq!(symmetric.advantage(), 0);
+        assert_eq!(symmetric.advantage_timeseries(5), vec![0; 5]);
+
+        let mut universe = Universe2D::new(6, 0);
+        universe.nodes[3].add_agents(30, AgentSpecies::Red);
+        universe.nodes[20].add_agents(10, AgentSpecies::Blue);
+        assert_eq!(universe.advantage(), 20);
+
+        universe.nodes[8].add_agents(50, AgentSpecies::Blue);
+        assert_eq!(universe.advantage_timeseries(3), vec![-30; 3]);
+    }
+
+    #[test]
+    fn test_graffiti_laplacian_and_gradient_around_spike() {
+        let mut universe = Universe2D::new(5, 0);
+        universe.nodes[12].graffiti.red = 4.0; // (2, 2)
+
+        let laplacian = universe.graffiti_laplacian(AgentSpecies::Red);
+        let gradient = universe.graffiti_gradient_magnitude(AgentSpecies::Red);
+
+        assert_eq!(laplacian.len(), 25);
+        for index in 0..25 {
+            let (expected_laplacian, expected_gradient) = match index {
+                12 => (-16.0, 0.0),             // the spike is a local maximum
+                7 | 11 | 13 | 17 => (4.0, 2.0), // its neighbours slope towards it
+                _ => (0.0, 0.0),
+            };
+            assert_eq!(
+                laplacian[index], expected_laplacian,
+                "laplacian at {}",
+                index
+            );
+            assert_eq!(gradient[index], expected_gradient, "gradient at {}", index);
+        }
+        assert!(universe
+            .graffiti_laplacian(AgentSpecies::Blue)
+            .iter()
+            .all(|value| *value == 0.0));
+    }
+
+    #[test]
+    fn test_fields_stay_row_major_after_compact() {
+        let mut universe = Universe2D::new(5, 0);
+        let active: Vec<bool> = (0..25).map(|cell| cell >= 5).collect();
+        universe.apply_mask(&active).unwrap();
+        universe.compact();
+        let spike = universe.node_index(2, 2).unwrap();
+        universe.nodes[spike].graffiti.red = 4.0;
+        universe.nodes[spike].push_strength.red = 5.0;
+
+        let laplacian = universe.graffiti_laplacian(AgentSpecies::Red);
+        let gradient = universe.graffiti_gradient_magnitude(AgentSpecies::Red);
+        let push_gradient = universe.push_gradient(AgentSpecies::Red);
+        assert_eq!(laplacian.len(), 25);
+        assert_eq!(
+            (laplacian[12], laplacian[17], laplacian[2]),
+            (-16.0, 4.0, 0.0)
+        );
+        assert_eq!((gradient[13], gradient[12], gradient[2]), (2.0, 0.0, 0.0));
+        assert_eq!((push_gradient[11], push_gradient[2]), (5.0, 0.0));
+    }
+
+    #[test]
+    fn test_push_gradient_around_spike() {
+        let mut universe = Universe2D::new(5, 0);
+        for node in universe.nodes.iter_mut() {
+            node.push_strength.red = 1.0;
+        }
+        universe.nodes[12].push_strength.red = 5.0; // (2, 2)
+
+        let gradient = universe.push_gradient(AgentSpecies::Red);
+
+        assert_eq!(gradient.len(), 25);
+        for (index, value) in gradient.iter().enumerate() {
+            let expected = match index {
+                7 | 11 | 13 | 17 => 4.0, // neighbours of the spike
+                _ => 0.0,
+            };
+            assert_eq!(*value, expected, "gradient at {}", index);
+        }
+        assert!(universe
+            .push_gradient(AgentSpecies::Blue)
+            .iter()
+            .all(|value| *value == 0.0));
+    }
+
+    #[test]
+    fn test_agent_distribution_kurtosis() {
+        let spread = Universe2D::new(10, 5000);
+        let kurtosis = spread.agent_distribution_kurtosis(AgentSpecies::Red);
+        // independently placed agents are nearly Poisson distributed, excess kurtosis 1 / 50
+        assert!(kurtosis.abs() < 0.5, "kurtosis {}", kurtosis);
+
+        let mut peaked = Universe2D::new(10, 0);
+        peaked.nodes[42].add_agents(5000, AgentSpecies::Red);
+        peaked.nodes[7].add_agents(10, AgentSpecies::Red);
+        assert!(peaked.agent_distribution_kurtosis(AgentSpecies::Red) > 50.0);
+
+        assert!(peaked
+            .agent_distribution_kurtosis(AgentSpecies::Blue)
+            .is_nan());
+    }
+
+    #[test]
+    fn test_agent_quantiles() {
+        // counts 0..=24 on the 25 nodes, in scrambled order
+        let mut universe = Universe2D::new(5, 0);
+        let red: Vec<u32> = (0..25).map(|index| index * 7 % 25).collect();
+        universe.load_agents(&red, &[0; 25]).unwrap();
+
+        assert_eq!(
+            universe.agent_quantiles(AgentSpecies::Red, &[0.0, 0.5, 0.9, 1.0]),
+            vec![0, 12, 22, 24]
+        );
+        assert_eq!(
+            universe.agent_quantiles(AgentSpecies::Blue, &[0.5, 1.0]),
+            vec![0, 0]
+        );
+    }
+
+    #[test]
+    fn test_node_graffiti_timeseries() {
+        let mut universe = Universe2D::new(6, 200);
+        let mut replay = universe.clone();
+
+        let timeseries = universe.node_graffiti_timeseries(2, 3, 25);
+        assert_eq!(timeseries.len(), 25);
+        assert!(timeseries
+            .iter()
+            .all(|(red, blue)| *red >= 0.0 && *blue >= 0.0));
+        assert!(timeseries.iter().any(|(red, blue)| red + blue > 0.0));
+        assert_eq!(universe.iteration, 25);
+
+        assert_eq!(replay.node_graffiti_phase_portrait(2, 3, 25), timeseries);
+    }
+
+    #[test]
+    fn test_species_clusters() {
+        let mut universe = Universe2D::new(6, 0);
+        // columns 0 and 5 touch across the edge, column 2 is separate
+        for node in universe.nodes.iter_mut() {
+            if [0, 2, 5].contains(&(node.index % 6)) {
+                node.graffiti.red = 1.0;
+            }
+        }
+
+        let mut clusters = universe.species_clusters(AgentSpecies::Red);
+        clusters.sort_by_key(|cluster| cluster.len());
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].len(), 6);
+        assert_eq!(clusters[1].len(), 12);
+        assert!(universe.species_clusters(AgentSpecies::Blue).is_empty());
+    }
+
+    #[test]
+    fn test_species_clusters_are_independent_of_node_order() {
+        let mut universe = Universe2D::new(6, 0);
+        for node in universe.nodes.iter_mut() {
+            if [0, 2, 5].contains(&(node.cell % 6)) {
+                node.graffiti.red = 1.0;
+                node.red_agents = node.cell;
+            }
+        }
+        let clusters = universe.species_clusters(AgentSpecies::Red);
+        assert_eq!(clusters[0][0], 0);
+        assert_eq!(clusters[1][0], 2);
+
+        universe.sort_nodes_by_red_agents();
+        assert_eq!(universe.species_clusters(AgentSpecies::Red), clusters);
+        universe.compact();
+        assert_eq!(universe.species_clusters(AgentSpecies::Red), clusters);
+    }
+
+    #[test]
+    fn test_domain_count_coarsens() {
+        let mut universe = Universe2D::new(24, 24 * 24 * 3);
+        universe.set_hyper_params(HyperParams::new(0.5, 0.1, 0.2));
+
+        let counts = universe.domain_count_timeseries(AgentSpecies::Red, 200);
+        assert_eq!(counts.len(), 200);
+        let mean = |counts: &[u32]| counts.iter().sum::<u32>() as f32 / counts.len() as f32;
+        let means: Vec<f32> = counts.chunks(50).map(mean).collect();
+        assert!(
+            means.windows(2).all(|pair| pair[0] >= pair[1]),
+            "{:?}",
+            means
+        );
+        assert!(means[0] > means[3]);
+    }
+
+    #[test]
+    fn test_divergence_from_snapshot() {
+        let entropy = |universe: &Universe2D| {
+            let counts: Vec<f32> = universe
+                .nodes
+                .iter()
+                .map(|node| (node.red_agents + node.blue_agents) as f32)
+                .collect();
+            let total: f32 = counts.iter().sum();
+            -counts
+                .iter()
+                .filter(|count| **count > 0.0)
+                .map(|count| count / total * (count / total).ln())
+                .sum::<f32>()
+        };
+
+        let mut universe = Universe2D::new(8, 200);
+        universe.nodes[0].add_agents(300, AgentSpecies::Red);
+        let reference = universe.snapshot();
+
+        assert!(
+            universe
+                .kl_divergence_from_snapshot(&reference)
+                .unwrap()
+                .abs()
+                < 1e-6
+        );
+        let cross_entropy = universe.cross_entropy_from_snapshot(&reference).unwrap();
+        assert!((cross_entropy - entropy(&universe)).abs() < 1e-5);
+
+        universe.iterate(20);
+        let divergence = universe.kl_divergence_from_snapshot(&reference).unwrap();
+        assert!(divergence > 0.1, "divergence {}", divergence);
+        let cross_entropy = universe.cross_entropy_from_snapshot(&reference).unwrap();
+        assert!((cross_entropy - divergence - entropy(&universe)).abs() < 1e-4);
+
+        // agents on a node that was empty in the reference are heavily penalised
+        let mut empty_reference = reference.clone();
+        empty_reference.nodes[5].red_agents = 0;
+        empty_reference.nodes[5].blue_agents = 0;
+        assert!(
+            universe
+                .kl_divergence_from_snapshot(&empty_reference)
+                .unwrap()
+                > divergence
+        );
+
+        assert_eq!(
+            universe.kl_divergence_from_snapshot(&Universe2D::new(4, 0).snapshot()),
+            Err(UniverseError::SizeMismatch {
+                expected: 8,
+                found: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_snapshot_divergence_after_reordering() {
+        let mut universe = Universe2D::new(6, 100);
+        universe.iterate(3);
+        let reference = universe.snapshot();
+
+        universe.sort_nodes_by_red_agents();
+        assert!(
+            universe
+                .kl_divergence_from_snapshot(&reference)
+                .unwrap()
+                .abs()
+                < 1e-6
+        );
+        assert!(
+            Universe2D::from_state(reference)
+                .unwrap()
+                .kl_divergence_from_snapshot(&universe.snapshot())
+                .unwrap()
+                .abs()
+                < 1e-6
+        );
+    }
+
+    #[test]
+    fn test_drift_velocity() {
+        let mut universe = Universe2D::new(32, 0);
+        // no graffiti, so only the direction weights bias the walk
+        universe.set_hyper_params(HyperParams::new(0.0, 0.0, 1.0));
+        universe.nodes[8 * 32 + 8].add_agents(2000, AgentSpecies::Red);
+        universe.set_movement_rule(DirectionWeighted::new([1.0, 3.0, 1.0, 1.0]));
+
+        // an agent moves right with probability 1/2 and left with 1/6
+        let (vx, vy) = universe.drift_velocity(AgentSpecies::Red, 10);
+        assert!((vx - 1.0 / 3.0).abs() < 0.05, "vx {}", vx);
+        assert!(vy.abs() < 0.05, "vy {}", vy);
+
+        assert_eq!(universe.drift_velocity(AgentSpecies::Blue, 5), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_ratio_field() {
+        let mut universe = Universe2D::new(4, 0);
+        universe.nodes[6].add_agents(3, AgentSpecies::Red);
+        universe.nodes[6].add_agents(1, AgentSpecies::Blue);
+        universe.nodes[9].add_agents(2, AgentSpecies::Blue);
+
+        let ratios = universe.ratio_field();
+        assert_eq!(ratios.len(), 16);
+        assert_eq!(ratios[6], 0.75);
+        assert_eq!(ratios[9], 0.0);
+        assert!(ratios[0].is_nan());
+    }
+
+    #[test]
+    fn test_richness_field() {
+        let mut universe = Universe2D::new(4, 0);
+        universe.nodes[6].add_agents(3, AgentSpecies::Red);
+        universe.nodes[6].add_agents(1, AgentSpecies::Blue);
+        universe.nodes[9].add_agents(2, AgentSpecies::Blue);
+
+        let richness = universe.richness_field();
+        assert_eq!(richness.len(), 16);
+        assert_eq!(richness[6], 2);
+        assert_eq!(richness[9], 1);
+        assert_eq!(richness[0], 0);
+    }
+
+    #[test]
+    fn test_ratio_gradient_across_front() {
+        // red on the left half, blue on the right half, with some mixing in each
+        let mut universe = Universe2D::new(8, 0);
+        for node in universe.nodes.iter_mut() {
+            let (majority, minority) = if node.index % 8 < 4 {
+                (AgentSpecies::Red, AgentSpecies::Blue)
+            } else {
+                (AgentSpecies::Blue, AgentSpecies::Red)
+            };
+            node.add_agents(3 + node.index % 3, majority);
+            node.add_agents(1, minority);
+        }
+
+        for y in 0..8 {
+            let (dx, dy) = universe.ratio_gradient(3, y);
+            assert!(dx < 0.0 && dx.abs() > 2.0 * dy.abs(), "({}, {})", dx, dy);
+            // the wrapped front at the left edge points the other way
+            let (dx, dy) = universe.ratio_gradient(0, y);
+            assert!(dx > 0.0 && dx.abs() > 2.0 * dy.abs(), "({}, {})", dx, dy);
+        }
+    }
+
+    #[test]
+    fn test_segregation_index() {
+        let mut universe = Universe2D::new(4, 0);
+        assert_eq!(universe.segregation_index(), 0.0);
+
+        for node in universe.nodes.iter_mut() {
+            if node.index % 4 < 2 {
+                node.add_agents(3, AgentSpecies::Red);
+            } else {
+                node.add_agents(3, AgentSpecies::Blue);
+            }
+        }
+        // only the blocks straddling the two boundaries are mixed
+        assert_eq!(universe.segregation_index(), 0.5);
+
+        for node in universe.nodes.iter_mut() {
+            node.add_agents(3 - node.red_agents, AgentSpecies::Red);
+            node.add_agents(3 - node.blue_agents, AgentSpecies::Blue);
+        }
+        assert_eq!(universe.segregation_index(), 0.0);
+    }
+
+    #[test]
+    fn test_measure_stronger_beta_segregates_more() {
+        let measure = |beta: f32| {
+            let mut universe = Universe2D::new(12, 12 * 12 * 8);
+            universe.set_hyper_params(HyperParams::new(0.5, 0.5, beta));
+            universe.simulate_to_equilibrium_and_measure(60, 20)
+        };
+
+        let weak = measure(0.01);
+        let strong = measure(1.0);
+
+        assert_eq!(weak.samples, 20);
+        assert!(
+            strong.mean_segregation_index > weak.mean_segregation_index + 0.2,
+            "weak {:?}, strong {:?}",
+            weak,
+            strong
+        );
+        assert!(strong.std_segregation_index >= 0.0);
+    }
+
+    #[test]
+    fn test_species_boundary_length() {
+        let mut universe = Universe2D::new(6, 0);
+        assert_eq!(universe.species_boundary_length(), 0);
+
+        for node in universe.nodes.iter_mut() {
+            if node.index % 6 < 3 {
+                node.graffiti.red = 1.0;
+            } else {
+                node.graffiti.blue = 1.0;
+            }
+        }
+        // on the torus the two halves share both the middle and the wrap-around column
+        assert_eq!(universe.species_boundary_length(), 2 * 6);
+        assert_eq!(universe.tick_stats().species_boundary_length, 2 * 6);
+
+        for node in universe.nodes.iter_mut() {
+            let (x, y) = (node.index % 6, node.index / 6);
+            node.graffiti.red = ((x + y) % 2) as f32;
+            node.graffiti.blue = 1.0 - node.graffiti.red;
+        }
+        assert_eq!(universe.species_boundary_length(), 2 * 6 * 6);
+    }
+
+    #[test]
+    fn test_interface_box_dimension_straight_line() {
+        let mut universe = Universe2D::new(64, 0);
+        assert_eq!(universe.interface_box_dimension(), 0.0);
+
+        for node in universe.nodes.iter_mut() {
+            if node.index % 64 < 32 {
+                node.graffiti.red = 1.0;
+            } else {
+                node.graffiti.blue = 1.0;
+            }
+        }
+        let dimension = universe.interface_box_dimension();
+        assert!((dimension - 1.0).abs() < 0.05, "dimension {}", dimension);
+
+        for node in universe.nodes.iter_mut() {
+            let (x, y) = (node.index % 64, node.index / 64);
+            node.graffiti.red = ((x + y) % 2) as f32;
+            node.graffiti.blue = 1.0 - node.graffiti.red;
+        }
+        let dimension = universe.interface_box_dimension();
+        assert!((dimension - 2.0).abs() < 0.05, "dimension {}", dimension);
+    }
+
+    #[test]
+    fn test_euler_characteristic() {
+        let mut universe = Universe2D::new(8, 0);
+        assert_eq!(universe.euler_characteristic(AgentSpecies::Red), 0);
+
+        // a simply connected territory; a half universe would wrap into a band, see below
+        for node in universe.nodes.iter_mut() {
+            if node.index % 8 < 4 && node.index / 8 < 4 {
+                node.graffiti.red = 1.0;
+            }
+        }
+        assert_eq!(universe.euler_characteristic(AgentSpecies::Red), 1);
+
+        // the left half of the universe is a band of columns, closing into a cylinder
+        for node in universe.nodes.iter_mut() {
+            node.graffiti.red = if node.index % 8 < 4 { 1.0 } else { 0.0 };
+        }
+        assert_eq!(universe.euler_characteristic(AgentSpecies::Red), 0);
+
+        // full coverage is the torus itself
+        for node in universe.nodes.iter_mut() {
+            node.graffiti.red = 1.0;
+        }
+        assert_eq!(universe.euler_characteristic(AgentSpecies::Red), 0);
+
+        // a ring around the centre 2x2 block
+        for node in universe.nodes.iter_mut() {
+            let (x, y) = (node.index % 8, node.index / 8);
+            let in_ring = (2..6).contains(&x)
+                && (2..6).contains(&y)
+                && !((3..5).contains(&x) && (3..5).contains(&y));
+            node.graffiti.red = if in_ring { 1.0 } else { 0.0 };
+        }
+        assert_eq!(universe.euler_characteristic(AgentSpecies::Red), 0);
+        assert_eq!(universe.euler_characteristic(AgentSpecies::Blue), 0);
+    }
+
+    struct AllToTheRight;
+
+    impl MovementRule for AllToTheRight {
+        fn distribute(&self, agents: u32, _push: &[f32; 4], _prng: &mut Rand32) -> [u32; 4] {
+            [0, agents, 0, 0]
+        }
+    }
+
+    #[test]
+    fn test_graffiti_centroid_trails_agents() {
+        let mut universe = Universe2D::new(16, 0);
+        assert_eq!(universe.graffiti_centroid(AgentSpecies::Red), None);
+        assert_eq!(universe.agent_centroid(AgentSpecies::Red), None);
+
+        universe.nodes[8 * 16 + 4].add_agents(100, AgentSpecies::Red);
+        universe.set_movement_rule(AllToTheRight);
+        universe.iterate(4);
+
+        let (agent_x, agent_y) = universe.agent_centroid(AgentSpecies::Red).unwrap();
+        let (graffiti_x, graffiti_y) = universe.graffiti_centroid(AgentSpecies::Red).unwrap();
+        assert!((agent_x - 8.0).abs() < 1e-3);
+        assert!((agent_y - 8.0).abs() < 1e-3);
+        assert!(
+            (4.0..agent_x).contains(&graffiti_x),
+            "graffiti at {}",
+            graffiti_x
+        );
+        assert!((graffiti_y - 8.0).abs() < 1e-3);
+        assert_eq!(universe.graffiti_centroid(AgentSpecies::Blue), None);
+    }
+
+    #[test]
+    fn test_node_removal_resilience() {
+        let mut universe = Universe2D::new(8, 300);
+        universe.set_hyper_params(HyperParams::new(0.5, 0.5, 0.2));
+
+        assert_eq!(universe.node_removal_resilience(0.0, 1), 1.0);
+        let resilience = universe.node_removal_resilience(0.3, 1);
+        assert!(resilience.is_finite() && resilience > 0.0);
+        assert_ne!(resilience, 1.0);
+
+        // the same cells are removed whatever the node order
+        let mut sorted = universe.clone();
+        sorted.sort_nodes_by_red_agents();
+        assert!((sorted.node_removal_resilience(0.3, 1) - resilience).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_node_removal_resilience_after_compact() {
+        let mut universe = Universe2D::new(6, 200);
+        universe.set_hyper_params(HyperParams::new(0.5, 0.5, 0.2));
+        let mut active = vec![true; 36];
+        active[14] = false;
+        universe.apply_mask(&active).unwrap();
+        universe.compact();
+
+        assert_eq!(universe.node_removal_resilience(0.0, 1), 1.0);
+        assert!(universe.node_removal_resilience(0.3, 1).is_finite());
+    }
+
+    #[test]
+    fn test_inter_tick_correlation() {
+        let correlation = |beta: f32| {
+            let mut universe = Universe2D::new(16, 16 * 16 * 3);
+            universe.set_hyper_params(HyperParams::new(0.5, 0.5, beta));
+            universe.inter_tick_correlation(100)
+        };
+
+        // strong avoidance builds territories that persist, without it the agents keep mixing
+        let (strong, weak) = (correlation(0.2), correlation(0.0));
+        assert!(strong > weak + 0.1, "strong {} weak {}", strong, weak);
+        assert!(strong < 1.0);
+
+        assert!(Universe2D::new(4, 10).inter_tick_correlation(2).is_nan());
+    }
+
+    #[test]
+    fn test_run_with_annealing() {
+        let mut segregated = Universe2D::new(16, 16 * 16 * 3);
+        segregated.set_hyper_params(HyperParams::new(0.5, 0.5, 0.5));
+        segregated.iterate(100);
+
+        let mut annealed = segregated.clone();
+        let stats = annealed.run_with_annealing(0.5, 0.01, 100);
+        assert_eq!(stats.len(), 100);
+        assert_eq!(stats[99].iteration, 200);
+        assert!((annealed.hyper_params.beta - 0.01).abs() < 1e-6);
+
+        let mut quenched = segregated.clone();
+        quenched.iterate(100);
+        assert!(
+            annealed.segregation_index() < quenched.segregation_index() - 0.1,
+            "annealed {} quenched {}",
+            annealed.segregation_index(),
+            quenched.segregation_index()
+        );
+    }
+
+    #[test]
+    fn test_run_ensemble() {
+        let seeds: Vec<u64> = (0..100).collect();
+        let stats = Universe2D::run_ensemble(8, 192, &seeds, 200, HyperParams::new(0.5, 0.5, 0.5));
+
+        assert_eq!(stats.runs, 100);
+        assert!(stats.mean_segregation > 0.5, "{:?}", stats);
+        assert!(stats.std_segregation > 0.0);
+        assert!(stats.min_segregation <= stats.p10);
+        assert!(stats.p10 <= stats.p50 && stats.p50 <= stats.p90);
+        assert!(stats.p90 <= stats.max_segregation);
+    }
+
+    #[test]
+    fn test_win_probability_symmetric() {
+        let (red, blue) =
+            Universe2D::win_probability(6, 36, HyperParams::new(0.5, 0.5, 0.5), 200, 200);
+
+        assert!(red + blue <= 1.0);
+        assert!(
+            (red - 0.5).abs() < 0.15,
+            "red wins {}, blue wins {}",
+            red,
+            blue
+        );
+        assert!(
+            (blue - 0.5).abs() < 0.15,
+            "red wins {}, blue wins {}",
+            red,
+            blue
+        );
+    }
+
+    #[test]
+    fn test_finite_size_scaling() {
+        let scaling =
+            Universe2D::finite_size_scaling(&[4, 8, 16], 2, 200, HyperParams::new(0.5, 0.5, 0.2));
+
+        let sizes: Vec<u32> = scaling.iter().map(|(size, _)| *size).collect();
+        assert_eq!(sizes, vec![4, 8, 16]);
+        // small grids cannot hold domains that are large compared to the 2x2 blocks
+        assert!(
+            scaling.windows(2).all(|pair| pair[0].1 < pair[1].1),
+            "{:?}",
+            scaling
+        );
+    }
+
+    #[test]
+    fn test_sensitivity_analysis_beta() {
+        let universe = Universe2D::new(12, 12 * 12 * 8);
+        let betas = [0.01, 0.03, 0.1, 0.2];
+
+        let stats = universe.sensitivity_analysis(SensitivityParam::Beta, &betas, 80);
+
+        assert_eq!(stats.len(), betas.len());
+        assert!(stats.iter().all(|stats| stats.iteration == 80));
+        assert!(stats
+            .windows(2)
+            .all(|pair| pair[0].segregation_index < pair[1].segregation_index));
+    }
+
+    #[test]
+    fn test_territory_incursion() {
+        let mut universe = Universe2D::new(8, 0);
+        let red: Vec<u32> = (0..64).map(|i| if i % 8 < 4 { 5 } else { 0 }).collect();
+        let blue: Vec<u32> = (0..64).map(|i| if i % 8 < 4 { 0 } else { 5 }).collect();
+        universe.load_agents(&red, &blue).unwrap();
+        universe.tick_batch(10);
+
+        assert_eq!(universe.territory_incursion(AgentSpecies::Red), 0);
+        assert_eq!(universe.territory_incursion(AgentSpecies::Blue), 0);
+
+        universe.iterate(3);
+
+        assert!(universe.territory_incursion(AgentSpecies::Red) > 0);
+        assert!(universe.territory_incursion(AgentSpecies::Blue) > 0);
+    }
+
+    #[test]
+    fn test_mutual_exclusion_index() {
+        let mut universe = Universe2D::new(8, 0);
+        assert_eq!(universe.mutual_exclusion_index(), 0.0);
+
+        let red: Vec<u32> = (0..64).map(|i| if i % 8 < 4 { 5 } else { 0 }).collect();
+        let blue: Vec<u32> = (0..64).map(|i| if i % 8 < 4 { 0 } else { 5 }).collect();
+        universe.load_agents(&red, &blue).unwrap();
+        assert!((universe.mutual_exclusion_index() - 1.0).abs() < 1e-6);
+
+        universe.load_agents(&[5; 64], &[5; 64]).unwrap();
+        assert!(universe.mutual_exclusion_index().abs() < 1e-6);
+
+        // walls hold no agents and do not count towards the expectation
+        let active: Vec<bool> = (0..64).map(|i| i % 8 < 4).collect();
+        universe.apply_mask(&active).unwrap();
+        assert!(universe.mutual_exclusion_index().abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_colocation_index() {
+        let mut universe = Universe2D::new(8, 0);
+        assert_eq!(universe.colocation_index(), 0.0);
+
+        let red: Vec<u32> = (0..64).map(|i| if i % 8 < 4 { 5 } else { 0 }).collect();
+        let blue: Vec<u32> = (0..64).map(|i| if i % 8 < 4 { 0 } else { 5 }).collect();
+        universe.load_agents(&red, &blue).unwrap();
+        assert_eq!(universe.colocation_index(), 0.0);
+
+        universe.load_agents(&[5; 64], &[5; 64]).unwrap();
+        assert_eq!(universe.colocation_index(), 0.5);
+
+        universe.load_agents(&[6; 64], &[2; 64]).unwrap();
+        assert_eq!(universe.colocation_index(), 0.25);
+    }
+
+    #[test]
+    fn test_step_response_decays() {
+        let mut universe = Universe2D::new(10, 500);
+        universe.iterate(50);
+
+        let response = universe.step_response(AgentSpecies::Red, 5, 5, 200, 40);
+        assert_eq!(response.len(), 40);
+        assert!(response[0] > 0.0);
+        let tail = response[30..]
+            .iter()
+            .map(|excess| excess.abs())
+            .sum::<f32>()
+            / 10.0;
+        assert!(tail < response[0] / 4.0);
+    }
+
+    #[test]
+    fn test_kl_divergence_from_uniform() {
+        let mut universe = Universe2D::new(6, 0);
+        assert_eq!(universe.kl_divergence_from_uniform(AgentSpecies::Blue), 0.0);
+
+        universe.load_agents(&[3; 36], &[0; 36]).unwrap();
+        assert!(universe.kl_divergence_from_uniform(AgentSpecies::Red).abs() < 1e-5);
+
+        let mut red = [0; 36];
+        red[7] = 100;
+        universe.load_agents(&red, &[0; 36]).unwrap();
+        assert!((universe.kl_divergence_from_uniform(AgentSpecies::Red) - 36f32.ln()).abs() < 1e-5);
+
+        // uniform over the active nodes, walls do not count
+        universe.load_agents(&[3; 36], &[0; 36]).unwrap();
+        let active: Vec<bool> = (0..36).map(|i| i % 6 < 3).collect();
+        universe.apply_mask(&active).unwrap();
+        assert!(universe.kl_divergence_from_uniform(AgentSpecies::Red).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_winding_number_x_counts_stripes() {
+        let mut universe = Universe2D::new(12, 0);
+        assert_eq!(universe.winding_number_x(AgentSpecies::Red, 0), 0);
+
+        for node in universe.nodes.iter_mut() {
+            // three red stripes two nodes wide, separated by blue stripes
+            if (node.index % 12) / 2 % 2 == 0 {
+                node.graffiti.red = 1.0;
+            } else {
+                node.graffiti.blue = 1.0;
+            }
+        }
+
+        assert_eq!(universe.winding_number_x(AgentSpecies::Red, 0), 3);
+        assert_eq!(universe.winding_number_x(AgentSpecies::Blue, 7), 3);
+    }
+
+    #[test]
+    fn test_flow_entropy() {
+        let mut universe = Universe2D::new(5, 0);
+        let mut red = [0; 25];
+        red[12] = 4000;
+        universe.load_agents(&red, &[0; 25]).unwrap();
+        assert_eq!(universe.flow_entropy(), 0.0);
+
+        universe.tick();
+
+        // without blue graffiti, the agents spread evenly over the four edges of their node
+        assert!((universe.flow_entropy() - 4f32.ln()).abs() < 0.01);
+        assert_eq!(universe.tick_stats().flow_entropy, universe.flow_entropy());
+    }
+
+    struct EvenSplit;
+
+    impl MovementRule for EvenSplit {
+        fn distribute(&self, agents: u32, _push: &[f32; 4], _prng: &mut Rand32) -> [u32; 4] {
+            // the remainder goes to the first directions, so every agent moves
+            core::array::from_fn(|direction| agents / 4 + ((direction as u32) < agents % 4) as u32)
+        }
+    }
+
+    #[test]
+    fn test_entropy_production_rate() {
+        let mut universe = Universe2D::new(8, 0);
+        assert_eq!(universe.entropy_production_rate(), 0.0);
+
+        // uniform push strengths and an even split balance every flow
+        universe.load_agents(&[8; 64], &[4; 64]).unwrap();
+        universe.set_movement_rule(EvenSplit);
+        universe.iterate(3);
+        assert_eq!(universe.entropy_production_rate(), 0.0);
+
+        let mut universe = Universe2D::new_from_random_voronoi(8, 4, 20, 3);
+        universe.set_hyper_params(HyperParams::new(0.5, 0.1, 0.5));
+        for _ in 0..10 {
+            universe.tick();
+            assert!(universe.entropy_production_rate() >= 0.0);
+        }
+        assert!(universe.entropy_production_rate() > 0.0);
+
+        // flows in only one direction count too, and stay finite
+        let mut universe = Universe2D::new(4, 0);
+        universe.load_agents(&[10; 16], &[0; 16]).unwrap();
+        universe.set_movement_rule(AllToTheRight);
+        universe.tick();
+        let production = universe.entropy_production_rate();
+        assert!(production.is_finite());
+        // 16 one-way pairs of 10 agents: 16 * 10 * ln(10.5 / 0.5)
+        assert!((production - 160.0 * 21.0_f32.ln()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_entropy_production_of_biased_field() {
+        let production = |weights: [f32; 4]| {
+            let mut universe = Universe2D::new(8, 0);
+            // no graffiti, so the push strengths stay flat
+            universe.set_hyper_params(HyperParams::new(0.0, 0.0, 1.0));
+            universe.load_agents(&[1000; 64], &[1000; 64]).unwrap();
+            universe.set_movement_rule(DirectionWeighted::new(weights));
+            universe.tick();
+            universe.entropy_production_rate()
+        };
+
+        // a flat field only produces entropy through sampling noise in the random moves
+        let flat = production([1.0; 4]);
+        let biased = production([1.0, 3.0, 1.0, 1.0]);
+        assert!(biased > 10.0 * flat, "biased {} flat {}", biased, flat);
+        let agents = 64.0 * 2000.0;
+        assert!(flat / agents < 0.01, "flat {} per agent", flat / agents);
+    }
+
+    #[test]
+    fn test_flow_matrix_sums() {
+        let mut universe = Universe2D::new(6, 0);
+        let mut active = vec![true; 36];
+        active[14] = false;
+        universe.apply_mask(&active).unwrap();
+        let red: Vec<u32> = (0..36)
+            .map(|index| (index * 7 % 5) * (index != 14) as u32)
+            .collect();
+        let blue: Vec<u32> = (0..36)
+            .map(|index| (index % 3) * (index != 14) as u32)
+            .collect();
+        universe.load_agents(&red, &blue).unwrap();
+        universe.tick();
+        let before: Vec<u32> = red
+            .iter()
+            .zip(&blue)
+            .map(|(red, blue)| red + blue)
+            .collect();
+
+        let matrix = universe.compute_flow_matrix();
+        let mut arrivals = vec![0; 36];
+        for (row, agents) in matrix.iter().zip(&before) {
+            assert!(row.len() <= 4);
+            assert_eq!(row.iter().map(|(_, count)| count).sum::<u32>(), *agents);
+            for (destination, count) in row {
+                arrivals[*destination as usize] += count;
+            }
+        }
+        for (index, arrived) in arrivals.into_iter().enumerate() {
+            let (red, blue) = universe.agents_at(index as u32 % 6, index as u32 / 6);
+            assert_eq!(arrived, red + blue);
+        }
+
+        let red_matrix = universe.compute_flow_matrix_for_species(AgentSpecies::Red);
+        for (row, agents) in red_matrix.iter().zip(&red) {
+            assert_eq!(row.iter().map(|(_, count)| count).sum::<u32>(), *agents);
+        }
+    }
+
+    #[test]
+    fn test_flow_matrix_is_independent_of_node_order() {
+        let mut universe = Universe2D::new(6, 40);
+        let mut active = vec![true; 36];
+        active[9] = false;
+        universe.apply_mask(&active).unwrap();
+        universe.tick();
+        let matrix = universe.compute_flow_matrix();
+        assert!(matrix[9].is_empty());
+
+        universe.sort_nodes_by_red_agents();
+        assert_eq!(universe.compute_flow_matrix(), matrix);
+        universe.compact();
+        assert_eq!(universe.compute_flow_matrix(), matrix);
+    }
+
+    #[test]
+    fn test_flux_through_column_with_rightward_drift() {
+        let mut universe = Universe2D::new(8, 0);
+        universe.load_agents(&[100; 64], &[0; 64]).unwrap();
+        universe.set_movement_rule(DirectionWeighted::new([1.0, 4.0, 1.0, 1.0]));
+        assert_eq!(universe.flux_through_column(3, AgentSpecies::Red), 0);
+
+        for _ in 0..3 {
+            universe.tick();
+            for x in 0..8 {
+                // on average 8 rows * 100 * (4 - 1) / 7 ≈ 343 agents per tick
+                let flux = universe.flux_through_column(x, AgentSpecies::Red);
+                assert!(
+                    (250..450).contains(&flux),
+                    "flux {} through column {}",
+                    flux,
+                    x
+                );
+            }
+            assert_eq!(universe.flux_through_column(0, AgentSpecies::Blue), 0);
+        }
+    }
+
+    #[test]
+    fn test_is_at_equilibrium() {
+        let mut symmetric = Universe2D::new(8, 0);
+        symmetric.load_agents(&[1000; 64], &[0; 64]).unwrap();
+        assert!(symmetric.is_at_equilibrium(0.0));
+        symmetric.iterate(3);
+        assert!(symmetric.is_at_equilibrium(0.25));
+
+        // a rightward drift sends about four times as many agents right as left
+        let mut driven = Universe2D::new(8, 0);
+        driven.load_agents(&[1000; 64], &[0; 64]).unwrap();
+        driven.set_movement_rule(DirectionWeighted::new([1.0, 4.0, 1.0, 1.0]));
+        driven.iterate(3);
+        assert!(!driven.is_at_equilibrium(0.25));
+    }
+
+    #[test]
+    fn test_agents_out_iter() {
+        let mut universe = Universe2D::new(7, 150);
+        assert_eq!(universe.total_flow(), 0);
+
+        universe.tick();
+
+        assert_eq!(universe.total_flow(), 300);
+        for flow in universe.agents_out_iter() {
+            assert!(flow.count > 0);
+            let from = universe
+                .node_index(flow.from_cell % 7, flow.from_cell / 7)
+                .unwrap();
+            assert!(universe.nodes[from]
+                .neighbours
+                .into_iter()
+                .any(|index| universe.node_cell(index as usize) == flow.to_cell));
+        }
+        let red_flow: u32 = universe
+            .agents_out_iter()
+            .filter(|flow| flow.species == AgentSpecies::Red)
+            .map(|flow| flow.count)
+            .sum();
+        assert_eq!(red_flow, 150);
+    }
+
+    #[test]
+    fn test_time_to_contact() {
+        let mut universe = Universe2D::new(10, 0);
+        let red: Vec<u32> = (0..100).map(|i| if i % 10 == 0 { 20 } else { 0 }).collect();
+        let blue: Vec<u32> = (0..100).map(|i| if i % 10 == 5 { 20 } else { 0 }).collect();
+        universe.load_agents(&red, &blue).unwrap();
+
+        assert_eq!(universe.clone().time_to_contact(1), None);
+
+        // the columns are 5 nodes apart, so the species need at least 3 ticks to meet
+        let contact = universe.time_to_contact(50).unwrap();
+        assert!((3..50).contains(&contact), "contact at {}", contact);
+        assert_eq!(universe.time_to_contact(50), Some(contact));
+    }
+}
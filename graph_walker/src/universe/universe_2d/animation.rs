@@ -0,0 +1,127 @@
+use std::{fs::File, io, path::Path};
+
+use gif::{Encoder, Frame, Repeat};
+
+use crate::{
+    agent_species::AgentSpecies,
+    universe::{Universe, Universe2D},
+};
+
+/**
+ * Width and height in pixels of one cell in the animation
+ */
+const CELL_PIXELS: u32 = 4;
+
+/**
+ * Global palette in the colours of `DisplayMode::GraffitiDominance`: walls and removed cells,
+ * nodes without a dominant species, blue and red dominated nodes
+ */
+const PALETTE: [u8; 12] = [0, 0, 0, 60, 170, 60, 40, 90, 220, 220, 50, 40];
+const WALL: u8 = 0;
+const NEUTRAL: u8 = 1;
+const BLUE: u8 = 2;
+const RED: u8 = 3;
+
+impl Universe2D {
+    /**
+     * Tick `iterations` times and write the graffiti dominance map after every `every`th tick
+     * as a frame of a looping animated GIF at `path`. The four colours index straight into a
+     * global palette, so frames need no quantization.
+     */
+    pub fn to_gif(
+        &mut self,
+        iterations: u32,
+        every: u32,
+        path: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        if every == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "every must be at least 1",
+            ));
+        }
+
+        let side = u16::try_from(self.size * CELL_PIXELS)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "universe too large"))?;
+        let mut encoder =
+            Encoder::new(File::create(path)?, side, side, &PALETTE).map_err(io::Error::other)?;
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(io::Error::other)?;
+
+        for tick in 1..=iterations {
+            self.tick();
+            if tick.is_multiple_of(every) {
+                let frame = Frame {
+                    width: side,
+                    height: side,
+                    buffer: self.gif_pixels().into(),
+                    ..Frame::default()
+                };
+                encoder.write_frame(&frame).map_err(io::Error::other)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Palette indices of the current dominance map, row by row, scaled up by `CELL_PIXELS`
+     */
+    fn gif_pixels(&self) -> Vec<u8> {
+        let cells: Vec<u8> = (0..self.size * self.size)
+            .map(
+                |cell| match self.node_index(cell % self.size, cell / self.size) {
+                    Some(index) if self.nodes[index].active => {
+                        match self.nodes[index].dominant_species() {
+                            None => NEUTRAL,
+                            Some(AgentSpecies::Blue) => BLUE,
+                            Some(AgentSpecies::Red) => RED,
+                        }
+                    }
+                    _ => WALL,
+                },
+            )
+            .collect();
+
+        let side = (self.size * CELL_PIXELS) as usize;
+        (0..side * side)
+            .map(|pixel| {
+                let (x, y) = (pixel % side, pixel / side);
+                let cell =
+                    (y / CELL_PIXELS as usize) * self.size as usize + x / CELL_PIXELS as usize;
+                cells[cell]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_animation {
+    use super::*;
+
+    #[test]
+    fn test_to_gif_frame_count() {
+        let path = std::env::temp_dir().join(format!("graph_walker_{}.gif", std::process::id()));
+        let mut universe = Universe2D::new(8, 100);
+        universe.to_gif(10, 3, &path).unwrap();
+        assert_eq!(universe.tick_stats().iteration, 10);
+
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(File::open(&path).unwrap())
+            .unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (32, 32));
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.buffer.len(), 32 * 32);
+            frames += 1;
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(frames, 3);
+
+        assert_eq!(
+            universe.to_gif(1, 0, &path).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread::{self, JoinHandle},
+};
+
+use crate::universe::{TickStats, Universe, Universe2D};
+
+impl Universe2D {
+    /**
+     * Run `ticks` ticks on a new thread, which hands back the universe when joined
+     */
+    pub fn run_in_background(mut self, ticks: u32) -> JoinHandle<Universe2D> {
+        thread::spawn(move || {
+            self.iterate(ticks);
+            self
+        })
+    }
+
+    /**
+     * Like `run_in_background`, additionally sending the `TickStats` over the returned channel
+     * after every `period` ticks. The run continues when the receiver is dropped.
+     */
+    pub fn run_in_background_with_channel(
+        mut self,
+        ticks: u32,
+        period: u32,
+    ) -> (JoinHandle<Universe2D>, Receiver<TickStats>) {
+        assert!(period > 0, "the reporting period must be at least 1");
+        let (sender, receiver) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            for tick in 1..=ticks {
+                self.tick();
+                if tick.is_multiple_of(period) {
+                    // a dropped receiver only means nobody is listening anymore
+                    let _ = sender.send(self.tick_stats());
+                }
+            }
+            self
+        });
+
+        (handle, receiver)
+    }
+}
+
+#[cfg(test)]
+mod test_background {
+    use crate::universe::{TickStats, Universe, Universe2D};
+
+    #[test]
+    fn test_run_in_background() {
+        let mut foreground = Universe2D::new(6, 50);
+        let handle = foreground.clone().run_in_background(20);
+        foreground.iterate(20);
+
+        let universe = handle.join().unwrap();
+        let stats = universe.tick_stats();
+        assert_eq!(stats.iteration, 20);
+        assert_eq!((stats.red_agents, stats.blue_agents), (50, 50));
+        assert_eq!(universe.state_fingerprint(), foreground.state_fingerprint());
+    }
+
+    #[test]
+    fn test_run_in_background_with_channel() {
+        let (handle, receiver) = Universe2D::new(6, 50).run_in_background_with_channel(10, 3);
+
+        let stats: Vec<TickStats> = receiver.iter().collect();
+        let universe = handle.join().unwrap();
+
+        let iterations: Vec<u32> = stats.iter().map(|stats| stats.iteration).collect();
+        assert_eq!(iterations, vec![3, 6, 9]);
+        assert!(stats.iter().all(|stats| stats.red_agents == 50));
+        assert_eq!(universe.tick_stats().iteration, 10);
+    }
+}
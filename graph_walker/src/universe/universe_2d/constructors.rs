@@ -0,0 +1,789 @@
+use oorandom::Rand32;
+
+use crate::{
+    agent_species::AgentSpecies,
+    hyper_params::HyperParams,
+    nodes::Node,
+    universe::{Universe, Universe2D, UniverseError},
+};
+
+/**
+ * Squared distance between two cells on a torus of width `size`
+ */
+fn toroidal_distance_squared(a: (u32, u32), b: (u32, u32), size: u32) -> u32 {
+    let dx = a.0.abs_diff(b.0).min(size - a.0.abs_diff(b.0));
+    let dy = a.1.abs_diff(b.1).min(size - a.1.abs_diff(b.1));
+
+    dx * dx + dy * dy
+}
+
+/**
+ * Sample of the standard normal distribution, with the Box-Muller transform
+ */
+fn standard_normal(prng: &mut Rand32) -> f32 {
+    let u1 = 1.0 - prng.rand_float(); // in (0, 1], so the logarithm is finite
+    let u2 = prng.rand_float();
+
+    (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+}
+
+impl Universe2D {
+    /**
+     * Create a universe partitioned into the Voronoi cells of `seed_count` random seed points.
+     * Seeds alternate between Red and Blue, and every node gets `agent_density` agents of the
+     * species of its nearest seed.
+     *
+     * # Examples
+     * ```
+     * use graph_walker::universe::Universe2D;
+     * let universe = Universe2D::new_from_random_voronoi(8, 4, 10, 42);
+     * assert_eq!(universe.tick_stats().red_agents + universe.tick_stats().blue_agents, 640);
+     * ```
+     */
+    pub fn new_from_random_voronoi(
+        size: u32,
+        seed_count: u32,
+        agent_density: u32,
+        seed: u64,
+    ) -> Universe2D {
+        assert!(seed_count > 0, "at least one voronoi seed is required");
+
+        let mut prng = Rand32::new(seed);
+        let mut universe = Universe2D::empty(size);
+
+        let seeds: Vec<((u32, u32), AgentSpecies)> = (0..seed_count)
+            .map(|id| {
+                let position = (prng.rand_range(0..size), prng.rand_range(0..size));
+                let species = if id % 2 == 0 {
+                    AgentSpecies::Red
+                } else {
+                    AgentSpecies::Blue
+                };
+                (position, species)
+            })
+            .collect();
+
+        for node in universe.nodes.iter_mut() {
+            let position = (node.index % size, node.index / size);
+            let (_, species) = seeds
+                .iter()
+                .min_by_key(|(seed_position, _)| {
+                    toroidal_distance_squared(position, *seed_position, size)
+                })
+                .unwrap();
+
+            node.add_agents(agent_density, *species);
+        }
+
+        universe
+    }
+
+    /**
+     * Create a universe of diagonal bands one node wide: every node gets `agents_per_node`
+     * agents, red where x + y is even and blue where it is odd
+     */
+    pub fn new_diagonal_stripe(
+        size: u32,
+        agents_per_node: u32,
+        hyper_params: HyperParams,
+    ) -> Universe2D {
+        let mut universe = Universe2D::empty(size);
+        universe.set_hyper_params(hyper_params);
+
+        for node in universe.nodes.iter_mut() {
+            let (x, y) = (node.index % size, node.index / size);
+            let species = if (x + y) % 2 == 0 {
+                AgentSpecies::Red
+            } else {
+                AgentSpecies::Blue
+            };
+
+            node.add_agents(agents_per_node, species);
+        }
+
+        universe
+    }
+
+    /**
+     * Create a universe tiled with `period_x` by `period_y` copies of a random
+     * `base_size` x `base_size` pattern, holding `agent_density` agents of each species per
+     * node on average. The grid is `base_size * period_x` wide and `base_size * period_y`
+     * high, so the initial agents repeat under a translation by `base_size` in x or y.
+     * Returns `UniverseError::SizeMismatch` if `period_x` and `period_y` differ, as the grid of
+     * a `Universe2D` is square.
+     */
+    pub fn new_periodic(
+        base_size: u32,
+        period_x: u32,
+        period_y: u32,
+        agent_density: u32,
+        hyper_params: HyperParams,
+    ) -> Result<Universe2D, UniverseError> {
+        if period_x != period_y {
+            return Err(UniverseError::SizeMismatch {
+                expected: period_x,
+                found: period_y,
+            });
+        }
+
+        let mut prng = Rand32::new(100);
+        let mut base = vec![(0, 0); (base_size * base_size) as usize];
+        for id in 0..base_size * base_size * agent_density * 2 {
+            let (red, blue) = &mut base[prng.rand_range(0..base_size * base_size) as usize];
+            if id % 2 == 0 {
+                *red += 1;
+            } else {
+                *blue += 1;
+            }
+        }
+
+        let size = base_size * period_x;
+        let mut universe = Universe2D::empty(size);
+        universe.set_hyper_params(hyper_params);
+        for node in universe.nodes.iter_mut() {
+            let (x, y) = (node.index % size, node.index / size);
+            let (red, blue) = base[((y % base_size) * base_size + x % base_size) as usize];
+            node.add_agents(red, AgentSpecies::Red);
+            node.add_agents(blue, AgentSpecies::Blue);
+        }
+
+        Ok(universe)
+    }
+
+    /**
+     * Create a universe set up like the Schelling model of segregation: every cell of an
+     * `n` x `n` grid holds a single agent with probability `density`, red or blue with equal
+     * chance. A Schelling agent moves when less than `similarity_threshold` of its neighbours
+     * are of its own species; here beta = -ln(1 - `similarity_threshold`), so that a node
+     * holding one unit of opposing graffiti keeps a push strength equal to the tolerance
+     * 1 - `similarity_threshold`. A threshold of 0 disables avoidance.
+     *
+     * # Panics
+     * If `similarity_threshold` is not in [0, 1)
+     */
+    pub fn new_schelling_compatible(
+        n: u32,
+        density: f32,
+        similarity_threshold: f32,
+        seed: u64,
+    ) -> Universe2D {
+        assert!(
+            (0.0..1.0).contains(&similarity_threshold),
+            "the similarity threshold must be in [0, 1)"
+        );
+
+        let mut prng = Rand32::new(seed);
+        let mut universe = Universe2D::empty(n);
+        universe.set_hyper_params(HyperParams::new(
+            0.5,
+            0.5,
+            -(1.0 - similarity_threshold).ln(),
+        ));
+
+        for node in universe.nodes.iter_mut() {
+            if prng.rand_float() < density {
+                let species = if prng.rand_float() < 0.5 {
+                    AgentSpecies::Red
+                } else {
+                    AgentSpecies::Blue
+                };
+                node.add_agents(1, species);
+            }
+        }
+
+        universe
+    }
+
+    /**
+     * Create a universe with `total_agents` agents of `species` around (`mu_x`, `mu_y`), see
+     * `add_gaussian_agents`. Add a second Gaussian of the other species for a two-blob
+     * initial condition.
+     */
+    pub fn new_from_gaussian(
+        size: u32,
+        mu_x: f32,
+        mu_y: f32,
+        sigma: f32,
+        total_agents: u32,
+        species: AgentSpecies,
+        seed: u64,
+    ) -> Universe2D {
+        let mut universe = Universe2D::empty(size);
+        universe.add_gaussian_agents(mu_x, mu_y, sigma, total_agents, species, seed);
+        universe
+    }
+
+    /**
+     * Add `agents` agents of `species` at positions drawn from an isotropic 2D Gaussian around
+     * (`mu_x`, `mu_y`) with standard deviation `sigma` (in nodes), wrapping around the edges.
+     * Positions on walls or removed cells are drawn again.
+     *
+     * # Panics
+     * If the universe has no active nodes
+     */
+    pub fn add_gaussian_agents(
+        &mut self,
+        mu_x: f32,
+        mu_y: f32,
+        sigma: f32,
+        agents: u32,
+        species: AgentSpecies,
+        seed: u64,
+    ) {
+        assert!(
+            self.nodes.iter().any(|node| node.active),
+            "there is no active node to place agents on"
+        );
+
+        let mut prng = Rand32::new(seed);
+        let size = self.size as f32;
+        let mut placed = 0;
+        while placed < agents {
+            let x = (mu_x + sigma * standard_normal(&mut prng)).rem_euclid(size);
+            let y = (mu_y + sigma * standard_normal(&mut prng)).rem_euclid(size);
+            // rem_euclid can round up to `size` for tiny negative values
+            let (x, y) = ((x as u32).min(self.size - 1), (y as u32).min(self.size - 1));
+
+            if let Some(index) = self.node_index(x, y) {
+                if self.nodes[index].active {
+                    self.nodes[index].add_agents(1, species);
+                    placed += 1;
+                }
+            }
+        }
+    }
+
+    /**
+     * Create a universe on a Sierpinski carpet of `depth` levels: on every level the centre
+     * ninth of each 3x3 block is masked out as a wall. A `3^depth` wide grid keeps `8^depth`
+     * active nodes, each getting `agent_density` agents of both species.
+     */
+    pub fn new_fractal(
+        size: u32,
+        depth: u32,
+        agent_density: u32,
+        hyper_params: HyperParams,
+    ) -> Universe2D {
+        let mut universe = Universe2D::empty(size);
+        universe.set_hyper_params(hyper_params);
+
+        let in_carpet = |x: u32, y: u32| {
+            (0..depth).all(|level| {
+                let scale = 3u32.pow(level);
+                !((x / scale) % 3 == 1 && (y / scale) % 3 == 1)
+            })
+        };
+        let active: Vec<bool> = (0..size * size)
+            .map(|cell| in_carpet(cell % size, cell / size))
+            .collect();
+
+        universe.apply_mask(&active).unwrap();
+        for node in universe.nodes.iter_mut().filter(|node| node.active) {
+            node.add_agents(agent_density, AgentSpecies::Red);
+            node.add_agents(agent_density, AgentSpecies::Blue);
+        }
+
+        universe
+    }
+
+    /**
+     * Create a universe with a given histogram of agent counts per node: for every
+     * `(agent_count, node_count)` entry of `red_hist`, `node_count` random nodes get
+     * `agent_count` red agents, and likewise for `blue_hist`. Both histograms must cover exactly
+     * `size * size` nodes.
+     */
+    pub fn new_from_histogram(
+        size: u32,
+        red_hist: &[(u32, u32)],
+        blue_hist: &[(u32, u32)],
+        seed: u64,
+    ) -> Result<Universe2D, UniverseError> {
+        let cells = (size * size) as usize;
+        for hist in [red_hist, blue_hist] {
+            let node_count: usize = hist.iter().map(|(_, nodes)| *nodes as usize).sum();
+            if node_count != cells {
+                return Err(UniverseError::LengthMismatch {
+                    expected: cells,
+                    found: node_count,
+                });
+            }
+        }
+
+        let mut prng = Rand32::new(seed);
+        let mut universe = Universe2D::empty(size);
+
+        for (hist, species) in [
+            (red_hist, AgentSpecies::Red),
+            (blue_hist, AgentSpecies::Blue),
+        ] {
+            // Fisher-Yates shuffle of the node order
+            let mut order: Vec<usize> = (0..cells).collect();
+            for i in (1..cells).rev() {
+                let j = prng.rand_range(0..i as u32 + 1) as usize;
+                order.swap(i, j);
+            }
+
+            let counts = hist
+                .iter()
+                .flat_map(|(agents, nodes)| std::iter::repeat_n(*agents, *nodes as usize));
+            for (index, agents) in order.into_iter().zip(counts) {
+                universe.nodes[index].add_agents(agents, species);
+            }
+        }
+
+        Ok(universe)
+    }
+
+    /**
+     * Create a copy of `base` with Gaussian noise added to every active node: standard deviation
+     * `sigma_agents` on both agent counts (rounded, and clamped to 0) and `sigma_graffiti` on both
+     * graffiti values (clamped to 0). For ensembles of slightly different initial conditions.
+     * Like `from_state`, the copy uses the default movement rule and has no recorders enabled.
+     */
+    pub fn new_perturbed_from(
+        base: &Universe2D,
+        seed: u64,
+        sigma_agents: f32,
+        sigma_graffiti: f32,
+    ) -> Universe2D {
+        let mut prng = Rand32::new(seed);
+        let mut universe = Universe2D::resume_state(base.clone().into_state());
+
+        let size = universe.size;
+        let hyper_params = universe.hyper_params;
+        for node in universe.nodes.iter_mut().filter(|node| node.active) {
+            let mut perturb_agents = |agents: u32| {
+                (agents as f32 + sigma_agents * standard_normal(&mut prng))
+                    .round()
+                    .max(0.0) as u32
+            };
+            node.red_agents = perturb_agents(node.red_agents);
+            node.blue_agents = perturb_agents(node.blue_agents);
+
+            node.graffiti.red =
+                (node.graffiti.red + sigma_graffiti * standard_normal(&mut prng)).max(0.0);
+            node.graffiti.blue =
+                (node.graffiti.blue + sigma_graffiti * standard_normal(&mut prng)).max(0.0);
+            node.update_push_strength(&hyper_params, size);
+        }
+
+        universe
+    }
+
+    /**
+     * Create an offspring of two universes of the same shape for evolutionary search: the node
+     * in cell i takes its agent counts, graffiti, pheromone and push strengths from `parent_a`
+     * where the row-major `mask[i]` is true and from `parent_b` otherwise. An empty mask draws
+     * a uniform crossover from `seed`, each cell coming from either parent with probability
+     * 1/2. Everything else, such as the hyper parameters, the iteration and the node order,
+     * comes from `parent_a`, reset as in `from_state`.
+     *
+     * Returns `UniverseError::SizeMismatch` if the parents differ in size,
+     * `UniverseError::LengthMismatch` if a non-empty mask does not have one entry per cell and
+     * `UniverseError::ActiveMismatch` for a cell that is a wall or removed in only one parent.
+     */
+    pub fn crossover(
+        parent_a: &Universe2D,
+        parent_b: &Universe2D,
+        mask: &[bool],
+        seed: u64,
+    ) -> Result<Universe2D, UniverseError> {
+        if parent_a.size != parent_b.size {
+            return Err(UniverseError::SizeMismatch {
+                expected: parent_a.size,
+                found: parent_b.size,
+            });
+        }
+        let (size, cells) = (parent_a.size, (parent_a.size * parent_a.size) as usize);
+        if !mask.is_empty() && mask.len() != cells {
+            return Err(UniverseError::LengthMismatch {
+                expected: cells,
+                found: mask.len(),
+            });
+        }
+
+        let active_node = |parent: &Universe2D, cell: u32| {
+            parent
+                .node_index(cell % size, cell / size)
+                .filter(|index| parent.nodes[*index].active)
+        };
+        for cell in 0..size * size {
+            if active_node(parent_a, cell).is_some() != active_node(parent_b, cell).is_some() {
+                return Err(UniverseError::ActiveMismatch { cell });
+            }
+        }
+
+        let mut prng = Rand32::new(seed);
+        let from_a: Vec<bool> = match mask.is_empty() {
+            true => (0..cells).map(|_| prng.rand_float() < 0.5).collect(),
+            false => mask.to_vec(),
+        };
+
+        let mut offspring = Universe2D::resume_state(parent_a.clone().into_state());
+        for node in offspring.nodes.iter_mut() {
+            if from_a[node.cell as usize] {
+                continue;
+            }
+            let Some(index) = active_node(parent_b, node.cell) else {
+                continue; // a wall in both parents
+            };
+
+            let parent = &parent_b.nodes[index];
+            node.red_agents = parent.red_agents;
+            node.blue_agents = parent.blue_agents;
+            node.graffiti = parent.graffiti;
+            node.pheromone = parent.pheromone;
+            node.push_strength = parent.push_strength;
+        }
+
+        Ok(offspring)
+    }
+}
+
+#[cfg(test)]
+mod test_constructors {
+    use std::collections::HashMap;
+
+    use oorandom::Rand32;
+
+    use crate::{
+        agent_species::AgentSpecies,
+        hyper_params::HyperParams,
+        nodes::Node,
+        universe::{Universe, Universe2D, UniverseError},
+    };
+
+    /**
+     * Reference Schelling tick on a torus: every agent with less than `threshold` of its
+     * occupied Moore neighbours of its own species moves to a random empty cell
+     */
+    fn schelling_tick(
+        grid: &mut [Option<AgentSpecies>],
+        n: u32,
+        threshold: f32,
+        prng: &mut Rand32,
+    ) {
+        let unhappy: Vec<usize> = (0..grid.len())
+            .filter(|&cell| {
+                let Some(species) = grid[cell] else {
+                    return false;
+                };
+                let (x, y) = (cell as u32 % n, cell as u32 / n);
+                let (mut same, mut occupied) = (0, 0);
+                for (dx, dy) in [(1, 0), (1, 1), (0, 1), (n - 1, 1)]
+                    .into_iter()
+                    .flat_map(|(dx, dy)| [(dx, dy), (n - dx, n - dy)])
+                {
+                    let neighbour = ((y + dy) % n * n + (x + dx) % n) as usize;
+                    if let Some(other) = grid[neighbour] {
+                        occupied += 1;
+                        if other == species {
+                            same += 1;
+                        }
+                    }
+                }
+                occupied > 0 && (same as f32) < threshold * occupied as f32
+            })
+            .collect();
+
+        for cell in unhappy {
+            let empty: Vec<usize> = (0..grid.len()).filter(|&c| grid[c].is_none()).collect();
+            if empty.is_empty() {
+                return;
+            }
+            let target = empty[prng.rand_range(0..empty.len() as u32) as usize];
+            grid[target] = grid[cell].take();
+        }
+    }
+
+    #[test]
+    fn test_new_from_random_voronoi() {
+        let universe = Universe2D::new_from_random_voronoi(10, 6, 7, 3);
+
+        for node in &universe.nodes {
+            // each node belongs to exactly one voronoi region
+            let is_red = node.red_agents == 7 && node.blue_agents == 0;
+            let is_blue = node.blue_agents == 7 && node.red_agents == 0;
+            assert!(
+                is_red ^ is_blue,
+                "node {} is in no single region",
+                node.index
+            );
+        }
+
+        let stats = universe.tick_stats();
+        assert_eq!(stats.red_agents + stats.blue_agents, 10 * 10 * 7);
+        assert!(stats.red_agents > 0);
+        assert!(stats.blue_agents > 0);
+    }
+
+    #[test]
+    fn test_new_diagonal_stripe() {
+        let mut universe = Universe2D::new_diagonal_stripe(12, 8, HyperParams::new(0.5, 0.5, 0.2));
+
+        assert_eq!(universe.agents_at(0, 0), (8, 0));
+        assert_eq!(universe.agents_at(1, 0), (0, 8));
+        assert_eq!(universe.agents_at(1, 1), (8, 0));
+        assert!(universe.segregation_index() < 0.05);
+
+        universe.iterate(300);
+
+        assert!(
+            universe.segregation_index() > 0.3,
+            "segregation index {}",
+            universe.segregation_index()
+        );
+    }
+
+    #[test]
+    fn test_new_periodic() {
+        let universe = Universe2D::new_periodic(5, 3, 3, 4, HyperParams::default()).unwrap();
+        assert_eq!(universe.size, 15);
+
+        for y in 0..15 {
+            for x in 0..15 {
+                let agents = universe.agents_at(x, y);
+                assert_eq!(agents, universe.agents_at((x + 5) % 15, y));
+                assert_eq!(agents, universe.agents_at(x, (y + 5) % 15));
+            }
+        }
+
+        // the base pattern is random, not uniform
+        assert_ne!(universe.agents_at(0, 0), universe.agents_at(1, 0));
+        let stats = universe.tick_stats();
+        assert_eq!(stats.red_agents, 9 * 25 * 4);
+        assert_eq!(stats.blue_agents, 9 * 25 * 4);
+    }
+
+    #[test]
+    fn test_new_periodic_rejects_non_square_tiling() {
+        assert_eq!(
+            Universe2D::new_periodic(4, 2, 3, 1, HyperParams::default()).unwrap_err(),
+            UniverseError::SizeMismatch {
+                expected: 2,
+                found: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_new_schelling_compatible() {
+        let (n, threshold) = (20, 0.5);
+        let mut universe = Universe2D::new_schelling_compatible(n, 0.7, threshold, 3);
+        assert!(universe
+            .nodes
+            .iter()
+            .all(|node| node.red_agents + node.blue_agents <= 1));
+        let stats = universe.tick_stats();
+        let agents = stats.red_agents + stats.blue_agents;
+        assert!((240..320).contains(&agents), "{} agents", agents);
+        assert!((universe.hyper_params.beta - 2f32.ln()).abs() < 1e-6);
+
+        // the reference Schelling model, started from the same agents
+        let mut grid: Vec<Option<AgentSpecies>> = universe
+            .nodes
+            .iter()
+            .map(|node| match (node.red_agents, node.blue_agents) {
+                (1, _) => Some(AgentSpecies::Red),
+                (_, 1) => Some(AgentSpecies::Blue),
+                _ => None,
+            })
+            .collect();
+        let mut prng = Rand32::new(3);
+        for _ in 0..20 {
+            schelling_tick(&mut grid, n, threshold, &mut prng);
+        }
+        let mut schelling = Universe2D::new(n, 0);
+        let count = |species| -> Vec<u32> {
+            grid.iter()
+                .map(|cell| (*cell == Some(species)) as u32)
+                .collect()
+        };
+        schelling
+            .load_agents(&count(AgentSpecies::Red), &count(AgentSpecies::Blue))
+            .unwrap();
+
+        // both segregate
+        let initial = universe.segregation_index();
+        universe.iterate(200);
+        assert!(
+            schelling.segregation_index() > initial + 0.1,
+            "schelling {} initial {}",
+            schelling.segregation_index(),
+            initial
+        );
+        assert!(
+            universe.segregation_index() > initial + 0.1,
+            "graph walker {} initial {}",
+            universe.segregation_index(),
+            initial
+        );
+    }
+
+    #[test]
+    fn test_new_from_gaussian() {
+        let (size, sigma) = (32, 3.0);
+        let mut universe =
+            Universe2D::new_from_gaussian(size, 30.0, 8.0, sigma, 2000, AgentSpecies::Red, 5);
+        universe.add_gaussian_agents(14.0, 20.0, sigma, 1000, AgentSpecies::Blue, 6);
+
+        let stats = universe.tick_stats();
+        assert_eq!((stats.red_agents, stats.blue_agents), (2000, 1000));
+
+        // a 2D Gaussian holds 1 - e^-2, about 86%, of its mass within 2 sigma
+        let within_two_sigma = |species, centre: (f32, f32)| -> u32 {
+            universe
+                .nodes_iter()
+                .filter(|(x, y, _)| {
+                    let cell = (*x as f32 + 0.5, *y as f32 + 0.5);
+                    let dx = (cell.0 - centre.0)
+                        .abs()
+                        .min(size as f32 - (cell.0 - centre.0).abs());
+                    let dy = (cell.1 - centre.1)
+                        .abs()
+                        .min(size as f32 - (cell.1 - centre.1).abs());
+                    dx * dx + dy * dy <= (2.0 * sigma) * (2.0 * sigma)
+                })
+                .map(|(_, _, node)| node.get_agents_with_species(&species))
+                .sum()
+        };
+        // the red blob wraps around the right edge
+        assert!(within_two_sigma(AgentSpecies::Red, (30.0, 8.0)) > 1600);
+        assert!(within_two_sigma(AgentSpecies::Blue, (14.0, 20.0)) > 800);
+        assert_eq!(within_two_sigma(AgentSpecies::Blue, (30.0, 8.0)), 0);
+    }
+
+    #[test]
+    fn test_new_fractal() {
+        for depth in 0..4 {
+            let size = 3u32.pow(depth);
+            let universe = Universe2D::new_fractal(size, depth, 2, HyperParams::default());
+
+            let active = universe.nodes.iter().filter(|node| node.active).count();
+            assert_eq!(active, 8usize.pow(depth));
+            let stats = universe.tick_stats();
+            assert_eq!(stats.red_agents, 2 * 8u32.pow(depth));
+            assert_eq!(stats.blue_agents, 2 * 8u32.pow(depth));
+        }
+
+        let mut universe = Universe2D::new_fractal(9, 2, 3, HyperParams::default());
+        assert!(!universe.nodes[9 + 1].active); // the centre of the top left 3x3 block
+        assert!(!universe.nodes[4 * 9 + 4].active);
+        universe.iterate(5);
+        assert_eq!(universe.agents_at(4, 4), (0, 0));
+    }
+
+    #[test]
+    fn test_new_perturbed_from() {
+        let mut base = Universe2D::new(10, 50);
+        base.iterate(10);
+
+        let perturbed = Universe2D::new_perturbed_from(&base, 7, 2.0, 0.05);
+        let (base_stats, stats) = (base.tick_stats(), perturbed.tick_stats());
+        assert_ne!(perturbed.state_fingerprint(), base.state_fingerprint());
+        assert_eq!(perturbed.iteration, base.iteration);
+
+        // 100 nodes with noise of standard deviation 2 change the total by about 20
+        assert!(stats.red_agents.abs_diff(base_stats.red_agents) < 100);
+        assert!(stats.blue_agents.abs_diff(base_stats.blue_agents) < 100);
+        for (node, base_node) in perturbed.nodes.iter().zip(&base.nodes) {
+            assert!(node.red_agents.abs_diff(base_node.red_agents) <= 10);
+            assert!((node.graffiti.blue - base_node.graffiti.blue).abs() <= 0.25);
+        }
+
+        let unperturbed = Universe2D::new_perturbed_from(&base, 7, 0.0, 0.0);
+        assert_eq!(unperturbed.state_fingerprint(), base.state_fingerprint());
+    }
+
+    #[test]
+    fn test_new_from_histogram() {
+        let red_hist = [(0, 20), (3, 10), (10, 6)];
+        let blue_hist = [(1, 30), (7, 6)];
+        let universe = Universe2D::new_from_histogram(6, &red_hist, &blue_hist, 11).unwrap();
+
+        let mut red_counts: HashMap<u32, u32> = HashMap::new();
+        let mut blue_counts: HashMap<u32, u32> = HashMap::new();
+        for node in &universe.nodes {
+            *red_counts.entry(node.red_agents).or_default() += 1;
+            *blue_counts.entry(node.blue_agents).or_default() += 1;
+        }
+        assert_eq!(red_counts, HashMap::from(red_hist));
+        assert_eq!(blue_counts, HashMap::from(blue_hist));
+
+        let reshuffled = Universe2D::new_from_histogram(6, &red_hist, &blue_hist, 12).unwrap();
+        assert_ne!(reshuffled.state_fingerprint(), universe.state_fingerprint());
+
+        assert_eq!(
+            Universe2D::new_from_histogram(6, &red_hist, &[(1, 35)], 11).unwrap_err(),
+            UniverseError::LengthMismatch {
+                expected: 36,
+                found: 35
+            }
+        );
+    }
+
+    #[test]
+    fn test_crossover() {
+        let mut parent_a = Universe2D::new(6, 40);
+        parent_a.iterate(5);
+        let mut parent_b = Universe2D::new(6, 90);
+        parent_b.iterate(8);
+
+        let mask: Vec<bool> = (0..36).map(|index| index % 3 == 0).collect();
+        let offspring = Universe2D::crossover(&parent_a, &parent_b, &mask, 0).unwrap();
+
+        for (index, node) in offspring.nodes.iter().enumerate() {
+            let parent = match mask[index] {
+                true => &parent_a.nodes[index],
+                false => &parent_b.nodes[index],
+            };
+            assert_eq!(node.red_agents, parent.red_agents);
+            assert_eq!(node.blue_agents, parent.blue_agents);
+            assert_eq!(node.graffiti.red, parent.graffiti.red);
+            assert_eq!(node.graffiti.blue, parent.graffiti.blue);
+            assert_eq!(node.push_strength.red, parent.push_strength.red);
+        }
+        assert_eq!(offspring.iteration, parent_a.iteration);
+
+        // without a mask each node comes from a random parent
+        let uniform = Universe2D::crossover(&parent_a, &parent_b, &[], 3).unwrap();
+        let from_a = uniform
+            .nodes
+            .iter()
+            .zip(&parent_a.nodes)
+            .filter(|(node, parent)| node.graffiti.red == parent.graffiti.red)
+            .count();
+        assert!((6..30).contains(&from_a), "{} nodes from parent a", from_a);
+    }
+
+    #[test]
+    fn test_crossover_pairs_reordered_parents_by_cell() {
+        let mut parent_a = Universe2D::new(6, 40);
+        parent_a.iterate(5);
+        let mut parent_b = Universe2D::new(6, 90);
+        parent_b.iterate(8);
+        let mut sorted_b = parent_b.clone();
+        sorted_b.sort_nodes_by_red_agents();
+
+        let offspring = Universe2D::crossover(&parent_a, &sorted_b, &[false; 36], 0).unwrap();
+        for (y, x) in (0..6).flat_map(|y| (0..6).map(move |x| (y, x))) {
+            assert_eq!(offspring.agents_at(x, y), parent_b.agents_at(x, y));
+        }
+
+        let mut walled_b = parent_b.clone();
+        let mut active = vec![true; 36];
+        active[8] = false;
+        walled_b.apply_mask(&active).unwrap();
+        assert_eq!(
+            Universe2D::crossover(&parent_a, &walled_b, &[], 0).unwrap_err(),
+            UniverseError::ActiveMismatch { cell: 8 }
+        );
+        assert_eq!(
+            Universe2D::crossover(&parent_a, &parent_b, &[true; 35], 0).unwrap_err(),
+            UniverseError::LengthMismatch {
+                expected: 36,
+                found: 35
+            }
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use alloc::vec::Vec;
+
+use crate::universe::{CompressedDelta, Universe2D, UniverseError};
+
+/**
+ * Agents, graffiti and pheromone of a node: (red, blue, red graffiti, blue graffiti,
+ * red pheromone, blue pheromone)
+ */
+type NodeValues = (u32, u32, f32, f32, f32, f32);
+
+/**
+ * The recorded deltas and the node values they were taken against
+ */
+#[derive(Debug, Clone)]
+pub(super) struct DeltaRecording {
+    previous: Vec<NodeValues>,
+    deltas: Vec<CompressedDelta>,
+}
+
+impl Universe2D {
+    fn node_values(&self) -> Vec<NodeValues> {
+        self.nodes
+            .iter()
+            .map(|node| {
+                (
+                    node.red_agents,
+                    node.blue_agents,
+                    node.graffiti.red,
+                    node.graffiti.blue,
+                    node.pheromone.red,
+                    node.pheromone.blue,
+                )
+            })
+            .collect()
+    }
+
+    /**
+     * Record the changes of every tick from now on, dropping any deltas not yet popped. The
+     * agents, graffiti and pheromone of the nodes are recorded; the flows of the tick
+     * (`agents_out` and `agents_in`) are not, so they are left as they are on replay.
+     */
+    pub fn start_delta_recording(&mut self) {
+        self.delta_recording = Some(DeltaRecording {
+            previous: self.node_values(),
+            deltas: Vec::new(),
+        });
+    }
+
+    /**
+     * Take the deltas recorded since the last call, one per tick, oldest first
+     */
+    pub fn pop_deltas(&mut self) -> Vec<CompressedDelta> {
+        match &mut self.delta_recording {
+            Some(recording) => core::mem::take(&mut recording.deltas),
+            None => Vec::new(),
+        }
+    }
+
+    /**
+     * Replay recorded deltas, advancing the iteration by one per delta. Push strengths are
+     * recalculated afterwards. The flows (`agents_out` and `agents_in`) are not part of the
+     * deltas and keep their values. Fails without changing anything if a delta refers to a
+     * node that does not exist.
+     */
+    pub fn apply_deltas(&mut self, deltas: &[CompressedDelta]) -> Result<(), UniverseError> {
+        let out_of_range = deltas
+            .iter()
+            .flatten()
+            .find(|(index, ..)| *index as usize >= self.nodes.len());
+        if let Some((node, ..)) = out_of_range {
+            return Err(UniverseError::NodeOutOfRange { node: *node });
+        }
+
+        for delta in deltas {
+            for (index, red, blue, red_graffiti, blue_graffiti, red_pheromone, blue_pheromone) in
+                delta
+            {
+                let node = &mut self.nodes[*index as usize];
+                node.red_agents = node.red_agents.wrapping_add(*red);
+                node.blue_agents = node.blue_agents.wrapping_add(*blue);
+                node.graffiti.red += red_graffiti;
+                node.graffiti.blue += blue_graffiti;
+                node.pheromone.red += red_pheromone;
+                node.pheromone.blue += blue_pheromone;
+            }
+            self.iteration += 1;
+        }
+
+        let (hyper_params, size) = (self.push_params(), self.size);
+        self.nodes
+            .iter_mut()
+            .for_each(|node| node.update_push_strength(&hyper_params, size));
+        Ok(())
+    }
+
+    /**
+     * Renumber the recording when the nodes `a` and `b` swap places: the values they are
+     * compared against and the node indices of the deltas not yet popped
+     */
+    pub(super) fn swap_delta_nodes(&mut self, a: u32, b: u32) {
+        let Some(recording) = &mut self.delta_recording else {
+            return;
+        };
+
+        recording.previous.swap(a as usize, b as usize);
+        for delta in recording.deltas.iter_mut() {
+            for entry in delta.iter_mut() {
+                if entry.0 == a {
+                    entry.0 = b;
+                } else if entry.0 == b {
+                    entry.0 = a;
+                }
+            }
+        }
+    }
+
+    /**
+     * Store the changes of the last tick, if recording
+     */
+    pub(super) fn record_delta(&mut self) {
+        if self.delta_recording.is_none() {
+            return;
+        }
+
+        let current = self.node_values();
+        let recording = self.delta_recording.as_mut().unwrap();
+        let delta: CompressedDelta = current
+            .iter()
+            .zip(recording.previous.iter())
+            .enumerate()
+            .filter(|(_, (current, previous))| current != previous)
+            .map(|(index, (current, previous))| {
+                (
+                    index as u32,
+                    current.0.wrapping_sub(previous.0),
+                    current.1.wrapping_sub(previous.1),
+                    current.2 - previous.2,
+                    current.3 - previous.3,
+                    current.4 - previous.4,
+                    current.5 - previous.5,
+                )
+            })
+            .collect();
+
+        recording.deltas.push(delta);
+        recording.previous = current;
+    }
+}
+
+#[cfg(test)]
+mod test_deltas {
+    use crate::{
+        hyper_params::HyperParams,
+        universe::{Universe, Universe2D, UniverseError},
+    };
+
+    #[test]
+    fn test_replayed_deltas_match_ticks() {
+        let mut universe = Universe2D::new(6, 100);
+        let mut hyper_params = HyperParams::new(0.4, 0.2, 0.1);
+        hyper_params.gamma2 = 0.3;
+        hyper_params.lambda2 = 0.05;
+        hyper_params.pheromone_weight = 0.5;
+        universe.set_hyper_params(hyper_params);
+        let mut replay = universe.clone();
+
+        universe.start_delta_recording();
+        universe.iterate(8);
+        let deltas = universe.pop_deltas();
+        assert_eq!(deltas.len(), 8);
+        assert!(universe.pop_deltas().is_empty());
+
+        replay.apply_deltas(&deltas).unwrap();
+
+        assert_eq!(replay.iteration, universe.iteration);
+        for (a, b) in universe.nodes.iter().zip(replay.nodes.iter()) {
+            assert_eq!((a.red_agents, a.blue_agents), (b.red_agents, b.blue_agents));
+            assert!((a.graffiti.red - b.graffiti.red).abs() < 1e-4);
+            assert!((a.graffiti.blue - b.graffiti.blue).abs() < 1e-4);
+            assert!((a.pheromone.red - b.pheromone.red).abs() < 1e-4);
+            assert!((a.pheromone.blue - b.pheromone.blue).abs() < 1e-4);
+            assert!((a.push_strength.red - b.push_strength.red).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_deltas_for_missing_nodes_are_rejected() {
+        let mut universe = Universe2D::new(4, 10);
+        let before = universe.state_fingerprint();
+        let deltas = vec![
+            vec![(0, 1, 0, 0.0, 0.0, 0.0, 0.0)],
+            vec![(16, 1, 0, 0.0, 0.0, 0.0, 0.0)],
+        ];
+
+        assert_eq!(
+            universe.apply_deltas(&deltas),
+            Err(UniverseError::NodeOutOfRange { node: 16 })
+        );
+        assert_eq!(universe.state_fingerprint(), before);
+        assert_eq!(universe.iteration, 0);
+    }
+
+    #[test]
+    fn test_masked_grid_deltas_skip_walls() {
+        let mut universe = Universe2D::new(6, 100);
+        let active: Vec<bool> = (0..36).map(|index| index % 6 < 2).collect();
+        universe.apply_mask(&active).unwrap();
+        universe.tick();
+
+        universe.start_delta_recording();
+        universe.tick();
+
+        let deltas = universe.pop_deltas();
+        assert!(deltas[0].len() <= 12);
+        assert!(deltas[0].iter().all(|(index, ..)| index % 6 < 2));
+    }
+}
@@ -0,0 +1,104 @@
+use std::io::Write;
+
+use crate::universe::{Universe, Universe2D};
+
+/**
+ * A float as a JSON number, `null` for values JSON cannot represent
+ */
+fn json_number(value: f32) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+impl Universe2D {
+    /**
+     * Run `ticks` ticks, writing after each one a line of JSON (NDJSON) with the `TickStats`
+     * and the nodes that hold agents as `[x, y, red_agents, blue_agents, red_graffiti,
+     * blue_graffiti]`
+     */
+    pub fn export_json_lines<W: Write>(
+        &mut self,
+        mut writer: W,
+        ticks: u32,
+    ) -> std::io::Result<()> {
+        for _ in 0..ticks {
+            self.tick();
+            let stats = self.tick_stats();
+
+            let nodes: Vec<String> = self
+                .nodes_iter()
+                .filter(|(_, _, node)| node.red_agents + node.blue_agents > 0)
+                .map(|(x, y, node)| {
+                    format!(
+                        "[{},{},{},{},{},{}]",
+                        x,
+                        y,
+                        node.red_agents,
+                        node.blue_agents,
+                        json_number(node.graffiti.red),
+                        json_number(node.graffiti.blue)
+                    )
+                })
+                .collect();
+
+            let fields = [
+                ("iteration", stats.iteration.to_string()),
+                ("red_agents", stats.red_agents.to_string()),
+                ("blue_agents", stats.blue_agents.to_string()),
+                ("red_graffiti", json_number(stats.red_graffiti)),
+                ("blue_graffiti", json_number(stats.blue_graffiti)),
+                ("segregation_index", json_number(stats.segregation_index)),
+                ("energy", json_number(stats.energy)),
+                (
+                    "species_boundary_length",
+                    stats.species_boundary_length.to_string(),
+                ),
+                ("flow_entropy", json_number(stats.flow_entropy)),
+                ("nodes", format!("[{}]", nodes.join(","))),
+            ];
+
+            write!(writer, "{{")?;
+            for (position, (name, value)) in fields.iter().enumerate() {
+                if position > 0 {
+                    write!(writer, ",")?;
+                }
+                write!(writer, "\"{}\":{}", name, value)?;
+            }
+            writeln!(writer, "}}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_export {
+    use crate::universe::{Universe, Universe2D};
+
+    #[test]
+    fn test_export_json_lines() {
+        let mut universe = Universe2D::new(4, 30);
+        let mut output: Vec<u8> = Vec::new();
+
+        universe.export_json_lines(&mut output, 5).unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[4]["iteration"], 5);
+        assert_eq!(lines[0]["red_agents"], 30);
+
+        let nodes = lines[4]["nodes"].as_array().unwrap();
+        let agents: u64 = nodes
+            .iter()
+            .map(|node| node[2].as_u64().unwrap() + node[3].as_u64().unwrap())
+            .sum();
+        assert_eq!(agents, 60);
+    }
+}
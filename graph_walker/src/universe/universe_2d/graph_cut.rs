@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+
+use crate::{agent_species::AgentSpecies, universe::Universe2D};
+
+/**
+ * Residual graph for a unit capacity max-flow, arcs are stored in pairs with their reverse
+ */
+struct FlowGraph {
+    heads: Vec<usize>,
+    capacities: Vec<u32>,
+    arcs: Vec<Vec<usize>>, // per vertex, the indices of its outgoing arcs
+}
+
+impl FlowGraph {
+    fn new(vertices: usize) -> FlowGraph {
+        FlowGraph {
+            heads: Vec::new(),
+            capacities: Vec::new(),
+            arcs: vec![Vec::new(); vertices],
+        }
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, capacity: u32, reverse_capacity: u32) {
+        self.arcs[from].push(self.heads.len());
+        self.heads.push(to);
+        self.capacities.push(capacity);
+
+        self.arcs[to].push(self.heads.len());
+        self.heads.push(from);
+        self.capacities.push(reverse_capacity);
+    }
+
+    /**
+     * Edmonds-Karp: augment along shortest paths until the sink is unreachable
+     */
+    fn max_flow(&mut self, source: usize, sink: usize) -> u32 {
+        let mut flow = 0;
+
+        loop {
+            let mut via_arc: Vec<Option<usize>> = vec![None; self.arcs.len()];
+            let mut queue = VecDeque::from([source]);
+            while let Some(vertex) = queue.pop_front() {
+                for &arc in &self.arcs[vertex] {
+                    let head = self.heads[arc];
+                    if self.capacities[arc] > 0 && head != source && via_arc[head].is_none() {
+                        via_arc[head] = Some(arc);
+                        queue.push_back(head);
+                    }
+                }
+            }
+
+            if via_arc[sink].is_none() {
+                return flow;
+            }
+
+            let mut bottleneck = u32::MAX;
+            let mut vertex = sink;
+            while let Some(arc) = via_arc[vertex] {
+                bottleneck = bottleneck.min(self.capacities[arc]);
+                vertex = self.heads[arc ^ 1];
+            }
+
+            let mut vertex = sink;
+            while let Some(arc) = via_arc[vertex] {
+                self.capacities[arc] -= bottleneck;
+                self.capacities[arc ^ 1] += bottleneck;
+                vertex = self.heads[arc ^ 1];
+            }
+            flow += bottleneck;
+        }
+    }
+}
+
+impl Universe2D {
+    /**
+     * Minimum number of edges of the neighbour graph to remove so that no path is left between
+     * a red-dominant and a blue-dominant node. Nodes without a dominant species may end up on
+     * either side. 0 if either species dominates nowhere.
+     */
+    pub fn graph_cut_value(&self) -> u32 {
+        let nodes = self.nodes.len();
+        let (source, sink) = (nodes, nodes + 1);
+        let mut graph = FlowGraph::new(nodes + 2);
+
+        for (i, j) in self.adjacency_edges() {
+            graph.add_edge(i as usize, j as usize, 1, 1);
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            match node.dominant_species() {
+                _ if !node.active => {}
+                Some(AgentSpecies::Red) => graph.add_edge(source, index, u32::MAX, 0),
+                Some(AgentSpecies::Blue) => graph.add_edge(index, sink, u32::MAX, 0),
+                None => {}
+            }
+        }
+
+        graph.max_flow(source, sink)
+    }
+}
+
+#[cfg(test)]
+mod test_graph_cut {
+    use crate::universe::{Universe, Universe2D};
+
+    #[test]
+    fn test_graph_cut_value_of_halves() {
+        let mut universe = Universe2D::new(6, 0);
+        assert_eq!(universe.graph_cut_value(), 0);
+
+        for node in universe.nodes.iter_mut() {
+            if node.index % 6 < 3 {
+                node.graffiti.red = 1.0;
+            } else {
+                node.graffiti.blue = 1.0;
+            }
+        }
+
+        // both the middle and the wrap-around boundary have to be cut
+        assert_eq!(universe.graph_cut_value(), 2 * 6);
+    }
+
+    #[test]
+    fn test_graph_cut_value_of_enclosed_node() {
+        let mut universe = Universe2D::new(5, 0);
+        for node in universe.nodes.iter_mut() {
+            node.graffiti.blue = 1.0;
+        }
+        universe.nodes[12].graffiti.red = 2.0;
+        assert_eq!(universe.graph_cut_value(), 4);
+
+        // a neutral ring around the red node does not make the cut any larger
+        for index in [6, 7, 8, 11, 13, 16, 17, 18] {
+            universe.nodes[index].graffiti.blue = 0.0;
+        }
+        assert_eq!(universe.graph_cut_value(), 4);
+    }
+}
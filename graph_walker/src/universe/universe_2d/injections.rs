@@ -0,0 +1,114 @@
+use crate::{agent_species::AgentSpecies, nodes::Node, universe::Universe2D};
+
+/**
+ * A source adding `count` agents of `species` at (x, y) on every `period`-th iteration
+ */
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Injection {
+    x: u32,
+    y: u32,
+    species: AgentSpecies,
+    count: u32,
+    period: u32,
+}
+
+impl Universe2D {
+    /**
+     * Inject `count` agents of `species` at (x, y) at the end of every tick that brings the
+     * iteration to a multiple of `period`. Injections accumulate; a cell that is a wall or was
+     * removed when the injection is due receives nothing.
+     */
+    pub fn add_periodic_injection(
+        &mut self,
+        x: u32,
+        y: u32,
+        species: AgentSpecies,
+        count: u32,
+        period: u32,
+    ) {
+        assert!(period > 0, "the injection period must be at least 1");
+        assert!(
+            x < self.size && y < self.size,
+            "({}, {}) is outside the grid",
+            x,
+            y
+        );
+
+        self.injections.push(Injection {
+            x,
+            y,
+            species,
+            count,
+            period,
+        });
+    }
+
+    /**
+     * Remove all periodic injections
+     */
+    pub fn clear_injections(&mut self) {
+        self.injections.clear();
+    }
+
+    /**
+     * Apply the injections due at the current iteration
+     */
+    pub(super) fn inject_agents(&mut self) {
+        for injection in &self.injections {
+            if !self.iteration.is_multiple_of(injection.period) {
+                continue;
+            }
+            let Some(index) = self.node_index(injection.x, injection.y) else {
+                continue;
+            };
+            let node = &mut self.nodes[index];
+            if node.active {
+                node.add_agents(injection.count, injection.species);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_injections {
+    use crate::{
+        agent_species::AgentSpecies,
+        universe::{Universe, Universe2D},
+    };
+
+    #[test]
+    fn test_periodic_injections() {
+        let mut universe = Universe2D::new(5, 10);
+        universe.add_periodic_injection(2, 2, AgentSpecies::Red, 7, 3);
+        universe.add_periodic_injection(0, 4, AgentSpecies::Blue, 5, 2);
+        universe.add_periodic_injection(4, 0, AgentSpecies::Blue, 1, 3);
+
+        let mut totals = Vec::new();
+        for _ in 0..6 {
+            universe.tick();
+            let stats = universe.tick_stats();
+            totals.push((stats.iteration, stats.red_agents, stats.blue_agents));
+        }
+        assert_eq!(
+            totals,
+            vec![
+                (1, 10, 10),
+                (2, 10, 15),
+                (3, 17, 16),
+                (4, 17, 21),
+                (5, 17, 21),
+                (6, 24, 27),
+            ]
+        );
+
+        // the agents are added after they moved, so they are still at the source
+        let mut fresh = Universe2D::new(5, 0);
+        fresh.add_periodic_injection(1, 3, AgentSpecies::Red, 4, 1);
+        fresh.tick();
+        assert_eq!(fresh.agents_at(1, 3), (4, 0));
+
+        fresh.clear_injections();
+        fresh.iterate(2);
+        assert_eq!(fresh.tick_stats().red_agents, 4);
+    }
+}
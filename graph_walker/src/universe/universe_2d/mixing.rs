@@ -0,0 +1,584 @@
+use std::collections::VecDeque;
+
+use crate::{
+    agent_species::AgentSpecies,
+    nodes::{Node, Node2D},
+    universe::Universe2D,
+};
+
+/**
+ * The power iteration of `lazy_walk_spectral_gap` stops once the residual |M v - λ v| of the
+ * unit vector v drops below this
+ */
+const POWER_ITERATION_TOLERANCE: f64 = 1e-9;
+
+/**
+ * Cap on the power iterations, for walks whose second and third eigenvalues nearly coincide
+ */
+const MAX_POWER_ITERATIONS: u32 = 5000;
+
+impl Universe2D {
+    /**
+     * Symmetric edge weights of the push-strength graph: the product of the mean push strength
+     * of both end nodes, for every edge of `adjacency_edges`
+     */
+    fn push_strength_weights(&self) -> Vec<(usize, usize, f64)> {
+        let push = |index: u32| {
+            let node = &self.nodes[index as usize];
+            (node.get_push_strength(&AgentSpecies::Red) as f64
+                + node.get_push_strength(&AgentSpecies::Blue) as f64)
+                / 2.0
+        };
+
+        self.adjacency_edges()
+            .into_iter()
+            .map(|(i, j)| (i as usize, j as usize, push(i) * push(j)))
+            .collect()
+    }
+
+    /**
+     * Spectral gap of the lazy random walk on the push-strength graph: 1 minus the second
+     * largest eigenvalue of (I + D^-1/2 W D^-1/2) / 2, found by power iteration orthogonal to
+     * the stationary direction until it converges to `POWER_ITERATION_TOLERANCE`
+     */
+    fn lazy_walk_spectral_gap(&self) -> f64 {
+        let weights = self.push_strength_weights();
+        let mut degrees = vec![0.0; self.nodes.len()];
+        for (i, j, weight) in &weights {
+            degrees[*i] += weight;
+            degrees[*j] += weight;
+        }
+
+        let total_degree: f64 = degrees.iter().sum();
+        if total_degree == 0.0 {
+            return 0.0;
+        }
+        // top eigenvector, proportional to the square root of the degrees
+        let stationary: Vec<f64> = degrees
+            .iter()
+            .map(|degree| (degree / total_degree).sqrt())
+            .collect();
+        let project_and_normalise = |vector: &mut Vec<f64>| {
+            let overlap: f64 = vector.iter().zip(&stationary).map(|(v, s)| v * s).sum();
+            vector
+                .iter_mut()
+                .zip(&stationary)
+                .for_each(|(v, s)| *v -= overlap * s);
+            let norm = vector.iter().map(|v| v * v).sum::<f64>().sqrt();
+            vector.iter_mut().for_each(|v| *v /= norm);
+        };
+
+        let mut vector: Vec<f64> = (0..self.nodes.len())
+            .map(|index| match degrees[index] {
+                degree if degree > 0.0 => (index as f64 * 0.618_034).fract() - 0.5,
+                _ => 0.0,
+            })
+            .collect();
+        project_and_normalise(&mut vector);
+
+        let mut eigenvalue = 1.0;
+        for _ in 0..MAX_POWER_ITERATIONS {
+            let mut next: Vec<f64> = vector.iter().map(|v| v / 2.0).collect();
+            for (i, j, weight) in &weights {
+                let normalised_weight = weight / (degrees[*i] * degrees[*j]).sqrt() / 2.0;
+                next[*i] += normalised_weight * vector[*j];
+                next[*j] += normalised_weight * vector[*i];
+            }
+
+            eigenvalue = next.iter().zip(&vector).map(|(n, v)| n * v).sum();
+            let residual = next
+                .iter()
+                .zip(&vector)
+                .map(|(n, v)| (n - eigenvalue * v).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            project_and_normalise(&mut next);
+            vector = next;
+            if residual < POWER_ITERATION_TOLERANCE {
+                break;
+            }
+        }
+
+        1.0 - eigenvalue
+    }
+
+    /**
+     * Spectral estimate of the number of steps before the lazy random walk on the push-strength
+     * graph is within total variation distance `epsilon` of its stationary distribution:
+     * (1/2) ln(1 / (2 epsilon)) / gap. This is a lower bound on the mixing time.
+     * `u32::MAX` if the graph is disconnected, or before the first tick has set the push
+     * strengths.
+     */
+    pub fn random_walk_mixing_time_estimate(&self, epsilon: f32) -> u32 {
+        let gap = self.lazy_walk_spectral_gap();
+        if gap <= 1e-12 {
+            return u32::MAX;
+        }
+
+        let estimate = 0.5 * (1.0 / (2.0 * epsilon as f64)).ln() / gap;
+        estimate.ceil().min(u32::MAX as f64) as u32
+    }
+
+    /**
+     * The neighbours (top, right, bottom, left) of `node` with the probability that a single
+     * agent of `species` moves there: proportional to the push strength of the other species,
+     * uniform where those are all zero (before the first tick)
+     */
+    fn transition_probabilities(&self, node: &Node2D, species: AgentSpecies) -> [(usize, f64); 4] {
+        let other_species = match species {
+            AgentSpecies::Red => AgentSpecies::Blue,
+            AgentSpecies::Blue => AgentSpecies::Red,
+        };
+
+        let mut transitions = [(0, 0.0); 4];
+        for (transition, neighbour_idx) in transitions.iter_mut().zip(node.neighbours) {
+            let neighbour = &self.nodes[neighbour_idx as usize];
+            *transition = (
+                neighbour_idx as usize,
+                neighbour.get_push_strength(&other_species) as f64,
+            );
+        }
+
+        let total_push: f64 = transitions.iter().map(|(_, push)| push).sum();
+        for (_, push) in transitions.iter_mut() {
+            *push = match total_push {
+                total if total > 0.0 => *push / total,
+                _ => 0.25,
+            };
+        }
+        transitions
+    }
+
+    /**
+     * Whether the agents are in an absorbing state of the movement: every agent can only move
+     * along edges bent back into its own node by a wall, as the push strength of the other
+     * species is zero at all open neighbours. A tick then leaves every agent where it is.
+     * Judged for the default `PushWeighted` rule and `hop_distance` 1 on the current push
+     * strengths, which the graffiti pass of the next tick recalculates.
+     */
+    pub fn is_absorbing_state(&self) -> bool {
+        self.nodes.iter().all(|node| {
+            [AgentSpecies::Red, AgentSpecies::Blue]
+                .into_iter()
+                .filter(|species| node.get_agents_with_species(species) > 0)
+                .all(|species| {
+                    self.transition_probabilities(node, species).iter().all(
+                        |&(neighbour, probability)| {
+                            neighbour == node.index as usize || probability == 0.0
+                        },
+                    )
+                })
+        })
+    }
+
+    /**
+     * Long-run fraction of time a single agent of `species` spends on each grid cell, row-major
+     * (y * size + x), moving as in `mean_first_passage_time`; 0 for walls and removed cells.
+     * Found by `iters` steps of power iteration p ← P^T p from the uniform distribution over the
+     * active nodes.
+     */
+    pub fn approximate_stationary_distribution(
+        &self,
+        species: AgentSpecies,
+        iters: u32,
+    ) -> Vec<f32> {
+        let active_count = self.nodes.iter().filter(|node| node.active).count();
+        let mut distribution: Vec<f64> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                if node.active {
+                    1.0 / active_count as f64
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let transitions: Vec<[(usize, f64); 4]> = self
+            .nodes
+            .iter()
+            .map(|node| self.transition_probabilities(node, species))
+            .collect();
+
+        for _ in 0..iters {
+            let mut next = vec![0.0; self.nodes.len()];
+            for (p, transitions) in distribution.iter().zip(&transitions) {
+                for (neighbour_idx, probability) in transitions {
+                    next[*neighbour_idx] += p * probability;
+                }
+            }
+            distribution = next;
+        }
+
+        let mut by_cell = vec![0.0; (self.size * self.size) as usize];
+        for (node, p) in self.nodes.iter().zip(distribution) {
+            by_cell[node.cell as usize] = p as f32;
+        }
+        by_cell
+    }
+
+    /**
+     * Expected number of steps for a single agent of `species` starting at (sx, sy) to first
+     * reach (tx, ty), moving to a neighbour with probability proportional to the push strength
+     * of the other species there (uniformly where those are all zero, before the first tick).
+     * Solves MFPT[i] = 1 + Σ_j P[i→j] MFPT[j] over the nodes that reach the target for sure,
+     * with the target absorbing, by Gaussian elimination on a banded matrix. The rows of the
+     * grid are numbered 0, size - 1, 1, size - 2, ... so that the neighbours across the torus
+     * edges are at most two rows apart, which bounds the band at 2 `size` either side of the
+     * diagonal: O(n `size`²) time and O(n `size`) memory for n active nodes, without pivoting
+     * since I - P is a diagonally dominant M-matrix. Infinite if the agent may never reach the
+     * target. Panics if either position is not an active node.
+     */
+    pub fn mean_first_passage_time(
+        &self,
+        sx: u32,
+        sy: u32,
+        tx: u32,
+        ty: u32,
+        species: AgentSpecies,
+    ) -> f32 {
+        let active_index = |x: u32, y: u32| {
+            (x < self.size && y < self.size)
+                .then(|| self.node_index(x, y))
+                .flatten()
+                .filter(|index| self.nodes[*index].active)
+                .unwrap_or_else(|| panic!("({}, {}) is not an active node", x, y))
+        };
+        let (source, target) = (active_index(sx, sy), active_index(tx, ty));
+        if source == target {
+            return 0.0;
+        }
+
+        let transitions: Vec<[(usize, f64); 4]> = self
+            .nodes
+            .iter()
+            .map(|node| self.transition_probabilities(node, species))
+            .collect();
+        let mut predecessors = vec![Vec::new(); self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if !node.active {
+                continue;
+            }
+            for (neighbour_idx, probability) in transitions[index] {
+                if probability > 0.0 && neighbour_idx != index {
+                    predecessors[neighbour_idx].push(index);
+                }
+            }
+        }
+
+        // Nodes that can reach the target
+        let mut reaches = vec![false; self.nodes.len()];
+        let mut queue = VecDeque::from([target]);
+        reaches[target] = true;
+        while let Some(index) = queue.pop_front() {
+            for &predecessor in &predecessors[index] {
+                if !reaches[predecessor] {
+                    reaches[predecessor] = true;
+                    queue.push_back(predecessor);
+                }
+            }
+        }
+
+        // Nodes that may move to a node never reaching the target miss it with positive
+        // probability, so their passage time is infinite as well
+        let mut trapped: Vec<bool> = reaches.iter().map(|reaches| !reaches).collect();
+        let mut queue: VecDeque<usize> = (0..self.nodes.len())
+            .filter(|index| trapped[*index])
+            .collect();
+        while let Some(index) = queue.pop_front() {
+            for &predecessor in &predecessors[index] {
+                if predecessor != target && !trapped[predecessor] {
+                    trapped[predecessor] = true;
+                    queue.push_back(predecessor);
+                }
+            }
+        }
+        if trapped[source] {
+            return f32::INFINITY;
+        }
+
+        // The remaining nodes only move to each other or the target, whose passage time is 0.
+        // Number them in the folded row order, which keeps the torus neighbours close.
+        let folded_row = |y: u32| {
+            if 2 * y < self.size {
+                2 * y
+            } else {
+                2 * (self.size - 1 - y) + 1
+            }
+        };
+        let mut unknowns: Vec<usize> = (0..self.nodes.len())
+            .filter(|index| *index != target && !trapped[*index])
+            .collect();
+        unknowns.sort_by_key(|index| {
+            let cell = self.node_cell(*index);
+            (folded_row(cell / self.size), cell % self.size)
+        });
+        let mut position = vec![None; self.nodes.len()];
+        for (row, index) in unknowns.iter().enumerate() {
+            position[*index] = Some(row);
+        }
+        // the unknowns each row depends on, with the probability of moving there
+        let rows: Vec<Vec<(usize, f64)>> = unknowns
+            .iter()
+            .map(|index| {
+                transitions[*index]
+                    .iter()
+                    .filter_map(|(neighbour_idx, probability)| {
+                        Some((position[*neighbour_idx]?, *probability))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // (I - P) m = 1 in band storage: entry (i, j) of the matrix is band[i][bandwidth + j - i]
+        let n = unknowns.len();
+        let bandwidth = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(row, columns)| columns.iter().map(move |(column, _)| column.abs_diff(row)))
+            .max()
+            .unwrap_or(0);
+        let width = 2 * bandwidth + 1;
+        let mut band = vec![0.0f64; n * width];
+        let mut mfpt = vec![1.0f64; n];
+        for (row, columns) in rows.iter().enumerate() {
+            band[row * width + bandwidth] = 1.0;
+            for (column, probability) in columns {
+                band[row * width + bandwidth + column - row] -= probability;
+            }
+        }
+
+        for pivot_row in 0..n {
+            let pivot = band[pivot_row * width + bandwidth];
+            for row in pivot_row + 1..n.min(pivot_row + bandwidth + 1) {
+                let factor = band[row * width + bandwidth + pivot_row - row] / pivot;
+                if factor == 0.0 {
+                    continue;
+                }
+                for column in pivot_row..n.min(pivot_row + bandwidth + 1) {
+                    band[row * width + bandwidth + column - row] -=
+                        factor * band[pivot_row * width + bandwidth + column - pivot_row];
+                }
+                mfpt[row] -= factor * mfpt[pivot_row];
+            }
+        }
+        for row in (0..n).rev() {
+            let mut steps = mfpt[row];
+            for column in row + 1..n.min(row + bandwidth + 1) {
+                steps -= band[row * width + bandwidth + column - row] * mfpt[column];
+            }
+            mfpt[row] = steps / band[row * width + bandwidth];
+        }
+
+        mfpt[position[source].expect("the source reaches the target")] as f32
+    }
+}
+
+#[cfg(test)]
+mod test_mixing {
+    use crate::{
+        agent_species::AgentSpecies,
+        hyper_params::HyperParams,
+        nodes::Node,
+        universe::{Universe, Universe2D, UniverseState},
+    };
+
+    /**
+     * Steps until the lazy walk started at node 0 is within `epsilon` of stationarity
+     */
+    fn measured_mixing_time(universe: &Universe2D, epsilon: f64) -> u32 {
+        let weights = universe.push_strength_weights();
+        let mut degrees = vec![0.0; universe.nodes.len()];
+        for (i, j, weight) in &weights {
+            degrees[*i] += weight;
+            degrees[*j] += weight;
+        }
+        let total_degree: f64 = degrees.iter().sum();
+
+        let mut distribution = vec![0.0; universe.nodes.len()];
+        distribution[0] = 1.0;
+        for step in 0.. {
+            let distance: f64 = distribution
+                .iter()
+                .zip(&degrees)
+                .map(|(p, degree)| (p - degree / total_degree).abs())
+                .sum::<f64>()
+                / 2.0;
+            if distance < epsilon {
+                return step;
+            }
+
+            let mut next: Vec<f64> = distribution.iter().map(|p| p / 2.0).collect();
+            for (i, j, weight) in &weights {
+                next[*j] += distribution[*i] * weight / degrees[*i] / 2.0;
+                next[*i] += distribution[*j] * weight / degrees[*j] / 2.0;
+            }
+            distribution = next;
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn test_mixing_time_estimate_bounds_measured_mixing_time() {
+        let mut universe = Universe2D::new(8, 300);
+        universe.iterate(5);
+
+        let estimate = universe.random_walk_mixing_time_estimate(0.01);
+        let measured = measured_mixing_time(&universe, 0.01);
+
+        assert!(estimate > 0);
+        assert!(estimate <= measured, "{} > {}", estimate, measured);
+        assert!(measured < 20 * estimate, "{} vs {}", measured, estimate);
+    }
+
+    #[test]
+    fn test_mixing_time_grows_with_size() {
+        let mut small = Universe2D::new(6, 0);
+        let mut large = Universe2D::new(12, 0);
+        // push strengths are only set by the first tick
+        small.tick();
+        large.tick();
+
+        let (small, large) = (
+            small.random_walk_mixing_time_estimate(0.1),
+            large.random_walk_mixing_time_estimate(0.1),
+        );
+        assert!(large > 2 * small);
+    }
+
+    #[test]
+    fn test_mean_first_passage_time_on_3x3_torus() {
+        let mut universe = Universe2D::new(3, 0);
+        // without graffiti every push strength is 1, so the walk is uniform
+        universe.tick();
+
+        // on the 3x3 torus, a neighbour of the target needs 8 steps on average and a diagonal
+        // node 10, from m_a = 1 + m_a / 4 + m_d / 2 and m_d = 1 + m_a / 2 + m_d / 2
+        let mfpt = |sx, sy| universe.mean_first_passage_time(sx, sy, 1, 1, AgentSpecies::Red);
+        assert_eq!(mfpt(1, 1), 0.0);
+        assert!((mfpt(2, 1) - 8.0).abs() < 1e-4, "{}", mfpt(2, 1));
+        assert!((mfpt(1, 0) - 8.0).abs() < 1e-4, "{}", mfpt(1, 0));
+        assert!((mfpt(0, 0) - 10.0).abs() < 1e-4, "{}", mfpt(0, 0));
+        assert!((mfpt(2, 2) - 10.0).abs() < 1e-4, "{}", mfpt(2, 2));
+    }
+
+    #[test]
+    fn test_mean_first_passage_time_on_large_torus() {
+        let mut universe = Universe2D::new(64, 0);
+        universe.tick();
+
+        // the mean return time of the uniform walk is the number of nodes, one step more than
+        // the passage time from any neighbour, also across the edges of the torus
+        for ((sx, sy), (tx, ty)) in [((6, 5), (5, 5)), ((63, 0), (0, 0)), ((0, 63), (0, 0))] {
+            let mfpt = universe.mean_first_passage_time(sx, sy, tx, ty, AgentSpecies::Blue);
+            assert!((mfpt - 4095.0).abs() < 1e-2, "{}", mfpt);
+        }
+    }
+
+    #[test]
+    fn test_mean_first_passage_time_unreachable() {
+        let mut universe = Universe2D::new(4, 0);
+        let mut mask = vec![true; 16];
+        // walls in columns 1 and 3 cut column 0 off from column 2
+        for y in 0..4 {
+            mask[y * 4 + 1] = false;
+            mask[y * 4 + 3] = false;
+        }
+        universe.apply_mask(&mask).unwrap();
+        universe.tick();
+
+        assert_eq!(
+            universe.mean_first_passage_time(0, 0, 2, 0, AgentSpecies::Blue),
+            f32::INFINITY
+        );
+        assert!(universe
+            .mean_first_passage_time(0, 0, 0, 3, AgentSpecies::Blue)
+            .is_finite());
+    }
+
+    #[test]
+    fn test_absorbing_state() {
+        // two neighbouring dead ends, each covered in graffiti of its own species
+        let mut universe = Universe2D::new(4, 0);
+        let hyper_params = HyperParams::new(0.0, 0.0, 1.0);
+        universe.set_hyper_params(hyper_params);
+        let active: Vec<bool> = (0..16).map(|cell| cell == 5 || cell == 6).collect();
+        universe.apply_mask(&active).unwrap();
+        universe.nodes[5].add_agents(7, AgentSpecies::Red);
+        universe.nodes[5].graffiti.red = 1000.0;
+        universe.nodes[6].add_agents(4, AgentSpecies::Blue);
+        universe.nodes[6].graffiti.blue = 1000.0;
+        for node in universe.nodes.iter_mut() {
+            node.update_push_strength(&hyper_params, 4);
+        }
+        assert!(universe.is_absorbing_state());
+
+        // a tick changes nothing but the recorded last moves of the agents bouncing back
+        let without_moves = |mut state: UniverseState| {
+            for node in state.nodes.iter_mut() {
+                node.red_agents_in = [0; 4];
+                node.blue_agents_in = [0; 4];
+            }
+            state
+        };
+        let before = without_moves(universe.clone().into_state());
+        universe.tick();
+        let mut after = without_moves(universe.clone().into_state());
+        after.iteration -= 1;
+        assert_eq!(after, before);
+
+        // without the graffiti the agents cross over
+        universe.nodes[6].graffiti.blue = 0.0;
+        universe.nodes[6].update_push_strength(&hyper_params, 4);
+        assert!(!universe.is_absorbing_state());
+        assert!(!Universe2D::new(4, 10).is_absorbing_state());
+    }
+
+    #[test]
+    fn test_stationary_distribution_matches_time_average() {
+        let mut universe = Universe2D::new(6, 0);
+        // without deposition or decay the graffiti, and so the walk, stays fixed
+        let hyper_params = HyperParams::new(0.0, 0.0, 1.0);
+        universe.set_hyper_params(hyper_params);
+        let red: Vec<u32> = vec![100; 36];
+        universe.load_agents(&red, &[0; 36]).unwrap();
+        for node in universe.nodes.iter_mut() {
+            node.graffiti.blue = (node.index % 7) as f32 / 3.0;
+            node.update_push_strength(&hyper_params, 6);
+        }
+
+        let stationary = universe.approximate_stationary_distribution(AgentSpecies::Red, 500);
+        assert!((stationary.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+
+        universe.iterate(50);
+        universe.enable_visit_counting();
+        universe.iterate(1000);
+        let visits = universe.visit_frequency(AgentSpecies::Red);
+        let total_visits: u64 = visits.iter().sum();
+        for (p, visits) in stationary.iter().zip(visits) {
+            let empirical = visits as f32 / total_visits as f32;
+            assert!((p - empirical).abs() < 0.1 * p, "{} vs {}", p, empirical);
+        }
+    }
+
+    #[test]
+    fn test_stationary_distribution_by_cell() {
+        let mut universe = Universe2D::new(5, 0);
+        let hyper_params = HyperParams::new(0.0, 0.0, 1.0);
+        let red: Vec<u32> = (0..25).map(|cell| (cell * 7) % 11).collect();
+        universe.load_agents(&red, &[0; 25]).unwrap();
+        for node in universe.nodes.iter_mut() {
+            node.graffiti.blue = (node.cell % 4) as f32;
+            node.update_push_strength(&hyper_params, 5);
+        }
+        let mut sorted = universe.clone();
+        sorted.sort_nodes_by_red_agents();
+
+        let stationary = universe.approximate_stationary_distribution(AgentSpecies::Red, 200);
+        assert_eq!(stationary.len(), 25);
+        let sorted_stationary = sorted.approximate_stationary_distribution(AgentSpecies::Red, 200);
+        for (p, sorted_p) in stationary.iter().zip(sorted_stationary) {
+            assert!((p - sorted_p).abs() < 1e-6, "{} vs {}", p, sorted_p);
+        }
+    }
+}
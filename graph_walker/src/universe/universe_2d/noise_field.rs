@@ -0,0 +1,132 @@
+use crate::{
+    agent_species::AgentSpecies,
+    noise::Perlin,
+    universe::{Universe2D, UniverseError},
+};
+
+impl Universe2D {
+    /**
+     * Add the entry of `noise` for the cell of every active node to its `species` graffiti,
+     * clamping at 0, and recalculate the push strengths. `noise` is row-major with one value per
+     * cell (y * size + x); the entries of walls and removed cells are ignored.
+     */
+    pub fn apply_noise_field(
+        &mut self,
+        noise: &[f32],
+        species: AgentSpecies,
+    ) -> Result<(), UniverseError> {
+        let expected = (self.size * self.size) as usize;
+        if noise.len() != expected {
+            return Err(UniverseError::LengthMismatch {
+                expected,
+                found: noise.len(),
+            });
+        }
+
+        let (hyper_params, size) = (self.push_params(), self.size);
+        for node in self.nodes.iter_mut().filter(|node| node.active) {
+            let graffiti = match species {
+                AgentSpecies::Red => &mut node.graffiti.red,
+                AgentSpecies::Blue => &mut node.graffiti.blue,
+            };
+            *graffiti = (*graffiti + noise[node.cell as usize]).max(0.0);
+            node.update_push_strength(&hyper_params, size);
+        }
+        Ok(())
+    }
+
+    /**
+     * Spatially correlated noise in about [-1, 1] for every cell, row-major: Perlin noise seeded
+     * with `seed`, sampled at the grid coordinates divided by `scale`, so that features are
+     * roughly `scale` nodes across. Scale it to the wanted amplitude before passing it to
+     * `apply_noise_field`.
+     */
+    pub fn generate_perlin_noise(&self, seed: u64, scale: f32) -> Vec<f32> {
+        let perlin = Perlin::new(seed);
+
+        (0..self.size * self.size)
+            .map(|cell| {
+                let (x, y) = (cell % self.size, cell / self.size);
+                perlin.noise(x as f32 / scale, y as f32 / scale)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_noise_field {
+    use crate::{
+        agent_species::AgentSpecies,
+        universe::{Universe, Universe2D, UniverseError},
+    };
+
+    #[test]
+    fn test_zero_noise_leaves_state_unchanged() {
+        let mut universe = Universe2D::new(8, 100);
+        universe.iterate(5);
+        let before = universe.clone().into_state();
+
+        universe
+            .apply_noise_field(&[0.0; 64], AgentSpecies::Red)
+            .unwrap();
+        assert_eq!(universe.clone().into_state(), before);
+
+        assert_eq!(
+            universe.apply_noise_field(&[0.0; 10], AgentSpecies::Red),
+            Err(UniverseError::LengthMismatch {
+                expected: 64,
+                found: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_perlin_noise_field() {
+        let mut universe = Universe2D::new(32, 0);
+        let noise = universe.generate_perlin_noise(3, 8.0);
+        assert_eq!(noise.len(), 32 * 32);
+        assert_eq!(noise, universe.generate_perlin_noise(3, 8.0));
+
+        // neighbouring nodes are much more alike than the field as a whole
+        let mean_step: f32 = (0..noise.len() - 1)
+            .map(|index| (noise[index + 1] - noise[index]).abs())
+            .sum::<f32>()
+            / (noise.len() - 1) as f32;
+        let mean = noise.iter().sum::<f32>() / noise.len() as f32;
+        let mean_deviation =
+            noise.iter().map(|value| (value - mean).abs()).sum::<f32>() / noise.len() as f32;
+        assert!(
+            mean_step < 0.5 * mean_deviation,
+            "{} {}",
+            mean_step,
+            mean_deviation
+        );
+
+        let amplified: Vec<f32> = noise.iter().map(|value| 2.0 * value).collect();
+        universe
+            .apply_noise_field(&amplified, AgentSpecies::Blue)
+            .unwrap();
+        for (node, value) in universe.nodes.iter().zip(&amplified) {
+            assert_eq!(node.graffiti.blue, value.max(0.0));
+            assert_eq!(node.graffiti.red, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_noise_field_by_cell() {
+        let mut universe = Universe2D::new(6, 50);
+        let mask: Vec<bool> = (0..36).map(|cell| cell % 6 != 3).collect();
+        universe.apply_mask(&mask).unwrap();
+        universe.sort_nodes_by_red_agents();
+        assert_eq!(universe.generate_perlin_noise(5, 2.0).len(), 36);
+
+        let noise: Vec<f32> = (0..36).map(|cell| cell as f32).collect();
+        universe
+            .apply_noise_field(&noise, AgentSpecies::Red)
+            .unwrap();
+        for node in &universe.nodes {
+            let expected = if node.active { node.cell as f32 } else { 0.0 };
+            assert_eq!(node.graffiti.red, expected);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{agent_species::AgentSpecies, universe::Universe2D};
+
+/**
+ * Ring buffer of the dominant species of every node over the last `window` ticks
+ */
+#[derive(Debug, Clone)]
+pub(super) struct OccupancyWindow {
+    window: usize,
+    recorded: usize, // number of filled slots, at most `window`
+    next_slot: usize,
+    dominant_species: Vec<Option<AgentSpecies>>, // `window` slots of one entry per node
+}
+
+impl Universe2D {
+    /**
+     * Start recording per node dominance over a sliding window of the last `n` ticks,
+     * restarting any earlier recording. 0 stops recording.
+     */
+    pub fn enable_occupancy_window(&mut self, n: u32) {
+        self.occupancy = (n > 0).then(|| OccupancyWindow {
+            window: n as usize,
+            recorded: 0,
+            next_slot: 0,
+            dominant_species: vec![None; n as usize * self.nodes.len()],
+        });
+    }
+
+    /**
+     * Fraction of the recorded ticks in the occupancy window in which the node at (x, y) was
+     * dominated by `species`, 0 if nothing was recorded or the cell has no node
+     */
+    pub fn occupancy(&self, x: u32, y: u32, species: AgentSpecies) -> f32 {
+        let (Some(occupancy), Some(index)) = (&self.occupancy, self.node_index(x, y)) else {
+            return 0.0;
+        };
+        if occupancy.recorded == 0 {
+            return 0.0;
+        }
+
+        let dominated = (0..occupancy.recorded)
+            .filter(|slot| {
+                occupancy.dominant_species[slot * self.nodes.len() + index] == Some(species)
+            })
+            .count();
+        dominated as f32 / occupancy.recorded as f32
+    }
+
+    /**
+     * Forget all recorded ticks, keeping the window size. Needed when nodes are renumbered.
+     */
+    pub(super) fn reset_occupancy_window(&mut self) {
+        if let Some(occupancy) = &self.occupancy {
+            self.enable_occupancy_window(occupancy.window as u32);
+        }
+    }
+
+    /**
+     * Exchange the recorded ticks of the nodes `a` and `b`, to follow nodes that swap places
+     */
+    pub(super) fn swap_occupancy(&mut self, a: usize, b: usize) {
+        let nodes = self.nodes.len();
+        if let Some(occupancy) = &mut self.occupancy {
+            for slot in 0..occupancy.window {
+                occupancy
+                    .dominant_species
+                    .swap(slot * nodes + a, slot * nodes + b);
+            }
+        }
+    }
+
+    /**
+     * Store the current dominant species of every node in the occupancy window, if enabled
+     */
+    pub(super) fn record_occupancy(&mut self) {
+        let Some(occupancy) = &mut self.occupancy else {
+            return;
+        };
+
+        let offset = occupancy.next_slot * self.nodes.len();
+        for (entry, node) in occupancy.dominant_species[offset..]
+            .iter_mut()
+            .zip(self.nodes.iter())
+        {
+            *entry = node.dominant_species();
+        }
+
+        occupancy.next_slot = (occupancy.next_slot + 1) % occupancy.window;
+        occupancy.recorded = (occupancy.recorded + 1).min(occupancy.window);
+    }
+}
+
+#[cfg(test)]
+mod test_occupancy {
+    use crate::{
+        agent_species::AgentSpecies,
+        universe::{Universe, Universe2D},
+    };
+
+    #[test]
+    fn test_occupancy_of_persistently_red_cell() {
+        let mut universe = Universe2D::new(4, 0);
+        let mut red = [0; 16];
+        red[5] = 50;
+        universe.load_agents(&red, &[0; 16]).unwrap();
+        // nothing recorded yet
+        assert_eq!(universe.occupancy(1, 1, AgentSpecies::Red), 0.0);
+
+        universe.enable_occupancy_window(3);
+        for node in universe.nodes.iter_mut() {
+            node.graffiti.red = 100.0;
+        }
+        universe.iterate(5);
+
+        assert_eq!(universe.occupancy(1, 1, AgentSpecies::Red), 1.0);
+        assert_eq!(universe.occupancy(1, 1, AgentSpecies::Blue), 0.0);
+        assert_eq!(universe.occupancy(3, 2, AgentSpecies::Red), 1.0);
+    }
+
+    #[test]
+    fn test_occupancy_window_forgets_old_ticks() {
+        let mut universe = Universe2D::new(2, 0);
+        universe.enable_occupancy_window(4);
+        universe.nodes[0].graffiti.blue = 1.0;
+        universe.record_occupancy();
+        universe.nodes[0].graffiti.blue = 0.0;
+        universe.record_occupancy();
+        assert_eq!(universe.occupancy(0, 0, AgentSpecies::Blue), 0.5);
+
+        for _ in 0..4 {
+            universe.record_occupancy();
+        }
+        assert_eq!(universe.occupancy(0, 0, AgentSpecies::Blue), 0.0);
+    }
+}
@@ -0,0 +1,133 @@
+use oorandom::Rand32;
+
+use crate::universe::Universe2D;
+
+/**
+ * Logistic birth and death of the agents, drawn from its own PRNG
+ */
+#[derive(Debug, Clone)]
+pub(super) struct BirthDeath {
+    birth_rate: f32,
+    death_rate: f32,
+    capacity: u32,
+    prng: Rand32,
+}
+
+impl Universe2D {
+    /**
+     * Let the agents reproduce and die at the end of every tick. On every active node each
+     * agent gives birth to an agent of its species with probability `birth_rate`, and dies with
+     * probability `death_rate` times the agents of both species on the node over `capacity`.
+     * The species compete for the room on the nodes, so the agent totals are no longer
+     * conserved; with equal rates a node settles around `capacity` agents. The draws come from
+     * a PRNG seeded with `seed`.
+     */
+    pub fn enable_birth_death(
+        &mut self,
+        birth_rate: f32,
+        death_rate: f32,
+        capacity: u32,
+        seed: u64,
+    ) {
+        assert!(
+            (0.0..=1.0).contains(&birth_rate) && (0.0..=1.0).contains(&death_rate),
+            "the birth and death rates must be probabilities"
+        );
+        assert!(capacity > 0, "the capacity must be at least 1");
+
+        self.birth_death = Some(BirthDeath {
+            birth_rate,
+            death_rate,
+            capacity,
+            prng: Rand32::new(seed),
+        });
+    }
+
+    /**
+     * Stop the births and deaths, the agent totals are conserved again
+     */
+    pub fn disable_birth_death(&mut self) {
+        self.birth_death = None;
+    }
+
+    /**
+     * Apply the births and deaths of one tick, visiting the cells in row-major order
+     */
+    pub(super) fn apply_birth_death(&mut self) {
+        let Some(mut birth_death) = self.birth_death.take() else {
+            return;
+        };
+
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let Some(index) = self.node_index(x, y) else {
+                    continue;
+                };
+                let node = &mut self.nodes[index];
+                if !node.active {
+                    continue;
+                }
+
+                let crowding =
+                    (node.red_agents + node.blue_agents) as f32 / birth_death.capacity as f32;
+                let death_probability = (birth_death.death_rate * crowding).min(1.0);
+                for agents in [&mut node.red_agents, &mut node.blue_agents] {
+                    let (mut births, mut deaths) = (0, 0);
+                    for _ in 0..*agents {
+                        if birth_death.prng.rand_float() < birth_death.birth_rate {
+                            births += 1;
+                        }
+                        if birth_death.prng.rand_float() < death_probability {
+                            deaths += 1;
+                        }
+                    }
+                    *agents = *agents + births - deaths;
+                }
+            }
+        }
+
+        self.birth_death = Some(birth_death);
+    }
+}
+
+#[cfg(test)]
+mod test_population {
+    use crate::universe::{Universe, Universe2D};
+
+    fn totals(universe: &Universe2D) -> (u32, u32) {
+        universe
+            .snapshot()
+            .nodes
+            .iter()
+            .fold((0, 0), |(red, blue), node| {
+                (red + node.red_agents, blue + node.blue_agents)
+            })
+    }
+
+    #[test]
+    fn test_birth_death_changes_the_totals() {
+        let mut universe = Universe2D::new(6, 100);
+        universe.enable_birth_death(0.2, 0.2, 2, 7);
+        universe.iterate(50);
+
+        // 200 agents on 36 nodes are crowded past the capacity of 2 per node and die back
+        let (red, blue) = totals(&universe);
+        assert!(red + blue < 200, "{} red, {} blue", red, blue);
+        assert!(red + blue > 0);
+
+        universe.disable_birth_death();
+        universe.iterate(10);
+        assert_eq!(totals(&universe), (red, blue));
+    }
+
+    #[test]
+    fn test_birth_death_is_reproducible() {
+        let run = || {
+            let mut universe = Universe2D::new(6, 20);
+            universe.enable_birth_death(0.1, 0.1, 2, 3);
+            universe.iterate(30);
+            universe.state_fingerprint()
+        };
+        assert_eq!(run(), run());
+    }
+}
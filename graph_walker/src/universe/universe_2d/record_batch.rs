@@ -0,0 +1,240 @@
+use std::{collections::HashMap, sync::Arc};
+
+use arrow::{
+    array::{Array, ArrayRef, BooleanArray, Float32Array, UInt32Array},
+    datatypes::{DataType, Field, Schema},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+
+use crate::universe::Universe2D;
+
+const U32_COLUMNS: [&str; 5] = ["index", "x", "y", "red_agents", "blue_agents"];
+const F32_COLUMNS: [&str; 2] = ["red_graffiti", "blue_graffiti"];
+
+/**
+ * Schema metadata key holding the side length of the grid
+ */
+const SIZE_KEY: &str = "size";
+
+/**
+ * The column `name` of `batch`, downcast to the array type `A`
+ */
+fn column<'a, A: Array + 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a A, ArrowError> {
+    batch
+        .column_by_name(name)
+        .ok_or_else(|| ArrowError::SchemaError(format!("missing column {}", name)))?
+        .as_any()
+        .downcast_ref::<A>()
+        .ok_or_else(|| ArrowError::SchemaError(format!("column {} has the wrong type", name)))
+}
+
+impl Universe2D {
+    /**
+     * The nodes as an Arrow record batch with the columns index, x, y, red_agents, blue_agents
+     * (u32), red_graffiti, blue_graffiti (f32) and active (bool), one row per node, walls
+     * included. The schema metadata `size` holds the side length of the grid.
+     */
+    pub fn to_record_batch(&self) -> RecordBatch {
+        let mut u32_columns: [Vec<u32>; 5] = Default::default();
+        let mut f32_columns: [Vec<f32>; 2] = Default::default();
+        let mut active = Vec::with_capacity(self.nodes.len());
+        for (index, node) in self.nodes.iter().enumerate() {
+            let cell = self.node_cell(index);
+            let row = [
+                node.index,
+                cell % self.size,
+                cell / self.size,
+                node.red_agents,
+                node.blue_agents,
+            ];
+            for (column, value) in u32_columns.iter_mut().zip(row) {
+                column.push(value);
+            }
+            f32_columns[0].push(node.graffiti.red);
+            f32_columns[1].push(node.graffiti.blue);
+            active.push(node.active);
+        }
+
+        let fields = U32_COLUMNS
+            .iter()
+            .map(|name| Field::new(*name, DataType::UInt32, false))
+            .chain(
+                F32_COLUMNS
+                    .iter()
+                    .map(|name| Field::new(*name, DataType::Float32, false)),
+            )
+            .chain([Field::new("active", DataType::Boolean, false)])
+            .collect::<Vec<_>>();
+        let columns = u32_columns
+            .into_iter()
+            .map(|values| Arc::new(UInt32Array::from(values)) as ArrayRef)
+            .chain(
+                f32_columns
+                    .into_iter()
+                    .map(|values| Arc::new(Float32Array::from(values)) as ArrayRef),
+            )
+            .chain([Arc::new(BooleanArray::from(active)) as ArrayRef])
+            .collect();
+        let metadata = HashMap::from([(SIZE_KEY.to_string(), self.size.to_string())]);
+
+        RecordBatch::try_new(
+            Arc::new(Schema::new(fields).with_metadata(metadata)),
+            columns,
+        )
+        .expect("the columns match the schema")
+    }
+
+    /**
+     * Rebuild a universe from a record batch in the layout of `to_record_batch`. The grid is
+     * `size` cells wide as stored in the schema metadata; cells without a row start empty and
+     * rows with active false become walls. The index column is not read, and push strengths
+     * follow from the graffiti with the default hyper parameters. Returns
+     * `ArrowError::InvalidArgumentError` for a missing or unreadable size and for a row
+     * outside the grid.
+     */
+    pub fn from_record_batch(batch: &RecordBatch) -> Result<Universe2D, ArrowError> {
+        let xs = column::<UInt32Array>(batch, "x")?;
+        let ys = column::<UInt32Array>(batch, "y")?;
+        let red_agents = column::<UInt32Array>(batch, "red_agents")?;
+        let blue_agents = column::<UInt32Array>(batch, "blue_agents")?;
+        let red_graffiti = column::<Float32Array>(batch, "red_graffiti")?;
+        let blue_graffiti = column::<Float32Array>(batch, "blue_graffiti")?;
+        let active = column::<BooleanArray>(batch, "active")?;
+
+        let size = batch
+            .schema()
+            .metadata()
+            .get(SIZE_KEY)
+            .and_then(|size| size.parse::<u32>().ok())
+            .filter(|size| size.checked_mul(*size).is_some())
+            .ok_or_else(|| {
+                ArrowError::InvalidArgumentError(format!(
+                    "the schema metadata {} is missing or not a valid grid size",
+                    SIZE_KEY
+                ))
+            })?;
+        let mut universe = Universe2D::empty(size);
+        let mut mask = vec![true; (size * size) as usize];
+        for row in 0..batch.num_rows() {
+            let (x, y) = (xs.value(row), ys.value(row));
+            if x >= size || y >= size {
+                return Err(ArrowError::InvalidArgumentError(format!(
+                    "row {} at ({}, {}) is outside the {}x{} grid",
+                    row, x, y, size, size
+                )));
+            }
+
+            let index = universe
+                .node_index(x, y)
+                .expect("an empty universe has a node in every cell");
+            let node = &mut universe.nodes[index];
+            node.red_agents = red_agents.value(row);
+            node.blue_agents = blue_agents.value(row);
+            node.graffiti.red = red_graffiti.value(row);
+            node.graffiti.blue = blue_graffiti.value(row);
+            mask[(y * size + x) as usize] = active.value(row);
+        }
+        universe
+            .apply_mask(&mask)
+            .expect("the mask has one entry per cell");
+
+        let hyper_params = universe.hyper_params;
+        for node in universe.nodes.iter_mut() {
+            node.update_push_strength(&hyper_params, size);
+        }
+        Ok(universe)
+    }
+}
+
+#[cfg(test)]
+mod test_record_batch {
+    use super::*;
+    use crate::universe::Universe;
+
+    #[test]
+    fn test_record_batch_round_trip() {
+        let mut universe = Universe2D::new(6, 40);
+        universe.iterate(5);
+
+        let batch = universe.to_record_batch();
+        assert_eq!(batch.num_rows(), 36);
+        assert_eq!(batch.num_columns(), 8);
+
+        let restored = Universe2D::from_record_batch(&batch).unwrap();
+        assert_eq!(restored.size, 6);
+        for (node, restored) in universe.nodes.iter().zip(&restored.nodes) {
+            assert_eq!(node.red_agents, restored.red_agents);
+            assert_eq!(node.blue_agents, restored.blue_agents);
+            assert_eq!(node.graffiti.red, restored.graffiti.red);
+            assert_eq!(node.graffiti.blue, restored.graffiti.blue);
+            assert_eq!(node.push_strength.red, restored.push_strength.red);
+        }
+    }
+
+    #[test]
+    fn test_from_record_batch_missing_column() {
+        let batch = Universe2D::new(3, 0).to_record_batch();
+        let without_x = batch.project(&[0, 2, 3, 4, 5, 6, 7]).unwrap();
+        assert!(matches!(
+            Universe2D::from_record_batch(&without_x),
+            Err(ArrowError::SchemaError(_))
+        ));
+    }
+
+    #[test]
+    fn test_record_batch_round_trip_with_walls() {
+        let mut universe = Universe2D::new(6, 40);
+        let mask: Vec<bool> = (0..36).map(|cell| cell % 6 != 2 && cell / 6 != 4).collect();
+        universe.apply_mask(&mask).unwrap();
+        universe.iterate(5);
+
+        let batch = universe.to_record_batch();
+        assert_eq!(batch.num_rows(), 36);
+
+        let restored = Universe2D::from_record_batch(&batch).unwrap();
+        assert_eq!(restored.size, 6);
+        for (node, restored) in universe.nodes.iter().zip(&restored.nodes) {
+            assert_eq!(node.active, restored.active);
+            assert_eq!(node.neighbours.top, restored.neighbours.top);
+            assert_eq!(node.neighbours.left, restored.neighbours.left);
+            assert_eq!(node.red_agents, restored.red_agents);
+            assert_eq!(node.blue_agents, restored.blue_agents);
+            assert_eq!(node.graffiti.red, restored.graffiti.red);
+        }
+    }
+
+    #[test]
+    fn test_from_record_batch_rejects_rows_outside_the_grid() {
+        let batch = Universe2D::new(3, 0).to_record_batch();
+        let mut xs: Vec<u32> = column::<UInt32Array>(&batch, "x")
+            .unwrap()
+            .values()
+            .to_vec();
+        xs[4] = u32::MAX;
+        let mut columns = batch.columns().to_vec();
+        columns[1] = Arc::new(UInt32Array::from(xs));
+        let out_of_range = RecordBatch::try_new(batch.schema(), columns).unwrap();
+
+        assert!(matches!(
+            Universe2D::from_record_batch(&out_of_range),
+            Err(ArrowError::InvalidArgumentError(_))
+        ));
+
+        let without_size = RecordBatch::try_new(
+            Arc::new(
+                batch
+                    .schema()
+                    .as_ref()
+                    .clone()
+                    .with_metadata(HashMap::new()),
+            ),
+            batch.columns().to_vec(),
+        )
+        .unwrap();
+        assert!(matches!(
+            Universe2D::from_record_batch(&without_size),
+            Err(ArrowError::InvalidArgumentError(_))
+        ));
+    }
+}
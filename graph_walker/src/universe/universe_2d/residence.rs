@@ -0,0 +1,92 @@
+use alloc::vec;
+
+use crate::{nodes::Node2D, universe::Universe2D};
+
+impl Universe2D {
+    /**
+     * Start counting, per node, the agent-ticks spent on it and the agents leaving it, from
+     * which `mean_residence_time` follows. Keeps existing counts.
+     */
+    pub fn enable_residence_tracking(&mut self) {
+        if self.residence_counts.is_none() {
+            self.residence_counts = Some(vec![(0, 0); self.nodes.len()]);
+        }
+    }
+
+    /**
+     * Mean number of consecutive ticks an agent stays on the node at (x, y) before moving on,
+     * both species together, since tracking was enabled. By Little's law this is the number of
+     * agent-ticks spent there divided by the number of departures. On the open grid every agent
+     * moves each tick, which gives 1; agents only stay when they bounce off a wall.
+     * NaN if tracking is disabled, the cell was removed, or no agent has left it yet.
+     */
+    pub fn mean_residence_time(&self, x: u32, y: u32) -> f32 {
+        let counts = self
+            .residence_counts
+            .as_ref()
+            .zip(self.node_index(x, y))
+            .map(|(counts, index)| counts[index]);
+
+        match counts {
+            Some((agent_ticks, departures)) if departures > 0 => {
+                agent_ticks as f32 / departures as f32
+            }
+            _ => f32::NAN,
+        }
+    }
+
+    /**
+     * Add the agents that moved out in the last tick to the residence counts, if tracking is
+     * enabled. Agents sent into a wall bounce back and count as staying.
+     */
+    pub(super) fn count_residence(&mut self, nodes_with_agents_out: &[Node2D]) {
+        let Some(counts) = &mut self.residence_counts else {
+            return;
+        };
+
+        for (node, (agent_ticks, departures)) in nodes_with_agents_out.iter().zip(counts) {
+            for (neighbour_idx, (red, blue)) in node
+                .neighbours
+                .into_iter()
+                .zip(node.agents_out[0].into_iter().zip(node.agents_out[1]))
+            {
+                *agent_ticks += (red + blue) as u64;
+                if neighbour_idx != node.index {
+                    *departures += (red + blue) as u64;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_residence {
+    use crate::{
+        agent_species::AgentSpecies,
+        nodes::Node,
+        universe::{Universe, Universe2D},
+    };
+
+    #[test]
+    fn test_mean_residence_time() {
+        let mut universe = Universe2D::new(6, 0);
+        // (1, 1) is a dead end, only open towards (1, 2); (4, 4) is in the open field
+        let mut active = vec![true; 36];
+        for (x, y) in [(0, 1), (2, 1), (1, 0)] {
+            active[y * 6 + x] = false;
+        }
+        universe.apply_mask(&active).unwrap();
+        universe.nodes[6 + 1].add_agents(400, AgentSpecies::Red);
+        universe.nodes[4 * 6 + 4].add_agents(400, AgentSpecies::Blue);
+
+        assert!(universe.mean_residence_time(1, 1).is_nan());
+        universe.enable_residence_tracking();
+        universe.iterate(20);
+
+        let flat = universe.mean_residence_time(4, 4);
+        let dead_end = universe.mean_residence_time(1, 1);
+        assert_eq!(flat, 1.0);
+        assert!(dead_end > 2.0, "dead end residence {}", dead_end);
+        assert!(universe.mean_residence_time(0, 1).is_nan());
+    }
+}
@@ -0,0 +1,77 @@
+use alloc::sync::Arc;
+
+use crate::{hyper_params::HyperParams, universe::Universe2D};
+
+/**
+ * Hyper parameters as a function of the iteration counter
+ */
+pub(super) type HyperParamsSchedule = Arc<dyn Fn(u32) -> HyperParams + Send + Sync>;
+
+impl Universe2D {
+    /**
+     * Let the hyper parameters follow `schedule`: every tick starts by setting them to
+     * `schedule(iteration)`, overriding `set_hyper_params`
+     */
+    pub fn set_hyper_params_schedule<F>(&mut self, schedule: F)
+    where
+        F: Fn(u32) -> HyperParams + Send + Sync + 'static,
+    {
+        self.schedule = Some(Arc::new(schedule));
+    }
+
+    /**
+     * Stop following the schedule, keeping the hyper parameters it last set
+     */
+    pub fn clear_hyper_params_schedule(&mut self) {
+        self.schedule = None;
+    }
+
+    /**
+     * The hyper parameters the next tick runs with: the schedule at the current iteration if
+     * one is set, the fixed hyper parameters otherwise
+     */
+    pub fn current_hyper_params(&self) -> HyperParams {
+        match &self.schedule {
+            Some(schedule) => schedule(self.iteration),
+            None => self.hyper_params,
+        }
+    }
+
+    /**
+     * Set the iteration counter, e.g. to resume a run rebuilt from external state at the time
+     * it was stopped. Only the counter changes: schedules and the recorded history pick up from
+     * `iteration`, the agents and graffiti are left as they are.
+     */
+    pub fn set_iteration(&mut self, iteration: u32) {
+        self.iteration = iteration;
+    }
+}
+
+#[cfg(test)]
+mod test_schedule {
+    use crate::{
+        hyper_params::HyperParams,
+        universe::{Universe, Universe2D},
+    };
+
+    fn beta_ramp(iteration: u32) -> HyperParams {
+        HyperParams::new(0.5, 0.5, iteration as f32 / 1000.0)
+    }
+
+    #[test]
+    fn test_schedule_follows_iteration() {
+        let mut universe = Universe2D::new(4, 20);
+        universe.set_hyper_params_schedule(beta_ramp);
+        assert_eq!(universe.current_hyper_params().beta, 0.0);
+
+        universe.set_iteration(100);
+        assert_eq!(universe.current_hyper_params(), beta_ramp(100));
+        universe.tick();
+        assert_eq!(universe.hyper_params, beta_ramp(100));
+        assert_eq!(universe.tick_stats().iteration, 101);
+
+        universe.clear_hyper_params_schedule();
+        universe.tick();
+        assert_eq!(universe.current_hyper_params(), beta_ramp(100));
+    }
+}
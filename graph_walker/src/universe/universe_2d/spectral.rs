@@ -0,0 +1,229 @@
+use crate::{agent_species::AgentSpecies, nodes::Node, universe::Universe2D};
+
+const SUBSPACE_ITERATIONS: u32 = 2000;
+const KMEANS_ITERATIONS: u32 = 100;
+
+/**
+ * Orthonormalise `vectors` in place with modified Gram-Schmidt
+ */
+fn orthonormalise(vectors: &mut [Vec<f64>]) {
+    for i in 0..vectors.len() {
+        let (previous, rest) = vectors.split_at_mut(i);
+        let vector = &mut rest[0];
+        for other in previous.iter() {
+            let overlap: f64 = vector.iter().zip(other).map(|(v, o)| v * o).sum();
+            vector
+                .iter_mut()
+                .zip(other)
+                .for_each(|(v, o)| *v -= overlap * o);
+        }
+        let norm = vector.iter().map(|v| v * v).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+    }
+}
+
+fn distance_squared(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum()
+}
+
+impl Universe2D {
+    /**
+     * Partition the active nodes into `k` territories by spectral clustering: the nodes are
+     * embedded with the eigenvectors of the `k` smallest eigenvalues of the normalised Laplacian
+     * I - D^-1/2 W D^-1/2, then grouped with k-means. Edges are weighted by the push strengths
+     * both end nodes share, Σ_species push_i * push_j, so they are weak across the boundary
+     * between territories of different species. Returns a cluster in 0..k per grid cell,
+     * row-major (y * size + x), `u32::MAX` for walls and removed cells. Deterministic and
+     * independent of the order of the nodes, but the cluster numbering is arbitrary.
+     */
+    pub fn spectral_clustering(&self, k: u32) -> Vec<u32> {
+        let k = k as usize;
+        // the active nodes in row-major order, which seeds the k-means below
+        let mut active: Vec<usize> = (0..self.nodes.len())
+            .filter(|index| self.nodes[*index].active)
+            .collect();
+        active.sort_by_key(|index| self.node_cell(*index));
+        assert!(
+            k > 0 && k <= active.len(),
+            "k must be between 1 and the number of active nodes"
+        );
+
+        let push = |index: u32| {
+            let node = &self.nodes[index as usize];
+            [
+                node.get_push_strength(&AgentSpecies::Red) as f64,
+                node.get_push_strength(&AgentSpecies::Blue) as f64,
+            ]
+        };
+        let weights: Vec<(usize, usize, f64)> = self
+            .adjacency_edges()
+            .into_iter()
+            .map(|(i, j)| {
+                let ([red_i, blue_i], [red_j, blue_j]) = (push(i), push(j));
+                (i as usize, j as usize, red_i * red_j + blue_i * blue_j)
+            })
+            .collect();
+        let mut degrees = vec![0.0; self.nodes.len()];
+        for (i, j, weight) in &weights {
+            degrees[*i] += weight;
+            degrees[*j] += weight;
+        }
+
+        // Subspace iteration on the lazy operator (I + D^-1/2 W D^-1/2) / 2, whose top
+        // eigenvectors are those of the smallest Laplacian eigenvalues
+        let mut vectors: Vec<Vec<f64>> = (0..k)
+            .map(|vector| {
+                (0..self.nodes.len())
+                    .map(|index| match degrees[index] {
+                        degree if degree > 0.0 => {
+                            let cell = self.node_cell(index) as usize;
+                            ((cell * (vector + 1)) as f64 * 0.618_034).fract() - 0.5
+                        }
+                        _ => 0.0,
+                    })
+                    .collect()
+            })
+            .collect();
+        orthonormalise(&mut vectors);
+        for _ in 0..SUBSPACE_ITERATIONS {
+            for vector in vectors.iter_mut() {
+                let mut next: Vec<f64> = vector.iter().map(|v| v / 2.0).collect();
+                for (i, j, weight) in &weights {
+                    let normalised_weight = weight / (degrees[*i] * degrees[*j]).sqrt() / 2.0;
+                    next[*i] += normalised_weight * vector[*j];
+                    next[*j] += normalised_weight * vector[*i];
+                }
+                *vector = next;
+            }
+            orthonormalise(&mut vectors);
+        }
+
+        // Row-normalised spectral embedding of the active nodes
+        let embedding: Vec<Vec<f64>> = active
+            .iter()
+            .map(|index| {
+                let row: Vec<f64> = vectors.iter().map(|vector| vector[*index]).collect();
+                let norm = row.iter().map(|v| v * v).sum::<f64>().sqrt();
+                match norm {
+                    norm if norm > 0.0 => row.iter().map(|v| v / norm).collect(),
+                    _ => row,
+                }
+            })
+            .collect();
+
+        // k-means, seeded with the farthest-point heuristic from the first node
+        let mut centroids = vec![embedding[0].clone()];
+        while centroids.len() < k {
+            let farthest = embedding
+                .iter()
+                .max_by(|a, b| {
+                    let nearest = |point: &Vec<f64>| {
+                        centroids
+                            .iter()
+                            .map(|centroid| distance_squared(point, centroid))
+                            .fold(f64::INFINITY, f64::min)
+                    };
+                    nearest(a).total_cmp(&nearest(b))
+                })
+                .unwrap();
+            centroids.push(farthest.clone());
+        }
+
+        let mut assignment = vec![0; embedding.len()];
+        for _ in 0..KMEANS_ITERATIONS {
+            let next_assignment: Vec<usize> = embedding
+                .iter()
+                .map(|point| {
+                    (0..k)
+                        .min_by(|a, b| {
+                            distance_squared(point, &centroids[*a])
+                                .total_cmp(&distance_squared(point, &centroids[*b]))
+                        })
+                        .unwrap()
+                })
+                .collect();
+            let converged = next_assignment == assignment;
+            assignment = next_assignment;
+
+            for (cluster, centroid) in centroids.iter_mut().enumerate() {
+                let members: Vec<&Vec<f64>> = embedding
+                    .iter()
+                    .zip(&assignment)
+                    .filter(|(_, assigned)| **assigned == cluster)
+                    .map(|(point, _)| point)
+                    .collect();
+                if members.is_empty() {
+                    continue;
+                }
+                for (dimension, value) in centroid.iter_mut().enumerate() {
+                    *value = members.iter().map(|point| point[dimension]).sum::<f64>()
+                        / members.len() as f64;
+                }
+            }
+            if converged {
+                break;
+            }
+        }
+
+        let mut clusters = vec![u32::MAX; (self.size * self.size) as usize];
+        for (index, cluster) in active.iter().zip(assignment) {
+            clusters[self.node_cell(*index) as usize] = cluster as u32;
+        }
+        clusters
+    }
+}
+
+#[cfg(test)]
+mod test_spectral {
+    use crate::{
+        agent_species::AgentSpecies,
+        hyper_params::HyperParams,
+        universe::{Universe, Universe2D},
+    };
+
+    #[test]
+    fn test_spectral_clustering_matches_species_territories() {
+        let mut universe = Universe2D::new(12, 0);
+        let hyper_params = HyperParams::new(0.5, 0.5, 1.0);
+        universe.set_hyper_params(hyper_params);
+        for node in universe.nodes.iter_mut() {
+            if (node.index % 12) < 6 {
+                node.graffiti.red = 5.0;
+            } else {
+                node.graffiti.blue = 5.0;
+            }
+            node.update_push_strength(&hyper_params, 12);
+        }
+
+        let clusters = universe.spectral_clustering(2);
+        let red_cluster = clusters[0];
+        for (node, cluster) in universe.nodes.iter().zip(&clusters) {
+            let in_red_territory = node.dominant_species() == Some(AgentSpecies::Red);
+            assert_eq!(
+                *cluster == red_cluster,
+                in_red_territory,
+                "node {}",
+                node.index
+            );
+        }
+        assert_eq!(universe.spectral_clustering(1), vec![0; 144]);
+    }
+
+    #[test]
+    fn test_spectral_clustering_by_cell() {
+        let mut universe = Universe2D::new(8, 100);
+        let mask: Vec<bool> = (0..64).map(|cell| cell % 8 != 5).collect();
+        universe.apply_mask(&mask).unwrap();
+        universe.iterate(20);
+        let mut compacted = universe.clone();
+        compacted.compact();
+        compacted.sort_nodes_by_red_agents();
+
+        let clusters = universe.spectral_clustering(3);
+        assert_eq!(clusters.len(), 64);
+        assert!((0..64).all(|cell| (clusters[cell] != u32::MAX) == mask[cell]));
+        assert_eq!(clusters, compacted.spectral_clustering(3));
+    }
+}
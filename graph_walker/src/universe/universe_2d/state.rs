@@ -0,0 +1,511 @@
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+
+use crate::{
+    hyper_params::HyperParams,
+    movement_rule::PushWeighted,
+    neighbour_data::{NeighbourAgentsOut2D, NeighbourData2D},
+    nodes::Node2D,
+    species::{SpeciesGraffiti, SpeciesPushStrength},
+    universe::{
+        ComputationType, DisplayMode, NodeState, Universe, Universe2D, UniverseError, UniverseState,
+    },
+};
+
+impl Universe2D {
+    /**
+     * Pause the universe, turning it into plain data that `from_state` resumes from
+     */
+    pub fn into_state(self) -> UniverseState {
+        self.snapshot()
+    }
+
+    /**
+     * The current state as plain data, like `into_state` but keeping the universe, e.g. as a
+     * reference to compare later states with
+     */
+    pub fn snapshot(&self) -> UniverseState {
+        let directions =
+            |agents: &NeighbourAgentsOut2D| [agents.top, agents.right, agents.bottom, agents.left];
+        let nodes = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| NodeState {
+                cell: self.node_cell(index),
+                neighbours: [
+                    node.neighbours.top,
+                    node.neighbours.right,
+                    node.neighbours.bottom,
+                    node.neighbours.left,
+                ],
+                red_agents: node.red_agents,
+                blue_agents: node.blue_agents,
+                red_graffiti: node.graffiti.red,
+                blue_graffiti: node.graffiti.blue,
+                red_pheromone: node.pheromone.red,
+                blue_pheromone: node.pheromone.blue,
+                red_agents_in: directions(&node.agents_in[0]),
+                blue_agents_in: directions(&node.agents_in[1]),
+                active: node.active,
+            })
+            .collect();
+
+        UniverseState {
+            size: self.size,
+            iteration: self.iteration,
+            hyper_params: self.hyper_params,
+            temperature: self.temperature,
+            nodes,
+        }
+    }
+
+    /**
+     * Run the same configuration serially, in parallel and in chunks of one row: a
+     * `size * size` universe with `agents` agents of each species placed from `seed`, ticked
+     * `iters` times with `hyper_params`. True if all runs end with the same
+     * `state_fingerprint`, so that CI can assert a parameter set is deterministic regardless of
+     * how the ticks are scheduled.
+     */
+    pub fn verify_reproducible(
+        size: u32,
+        agents: u32,
+        hyper_params: HyperParams,
+        seed: u64,
+        iters: u32,
+    ) -> bool {
+        let run = |computation| {
+            Universe2D::seeded_run_fingerprint(size, agents, hyper_params, seed, iters, computation)
+        };
+
+        let serial = run(ComputationType::Serial);
+        serial == run(ComputationType::Parallel)
+            && serial == run(ComputationType::Chunked(size as usize))
+    }
+
+    /**
+     * `state_fingerprint` after `iters` ticks with `computation` of a `size * size` universe
+     * with `agents` agents of each species placed from `seed` and `hyper_params`
+     */
+    pub(crate) fn seeded_run_fingerprint(
+        size: u32,
+        agents: u32,
+        hyper_params: HyperParams,
+        seed: u64,
+        iters: u32,
+        computation: ComputationType,
+    ) -> u64 {
+        let mut universe = Universe2D::new_seeded(size, agents, seed);
+        universe.set_hyper_params(hyper_params);
+        for _ in 0..iters {
+            universe.tick_with(computation);
+        }
+        universe.state_fingerprint()
+    }
+
+    /**
+     * Check that the universe is internally consistent, e.g. after `load_agents` or when resuming
+     * hand-built or deserialized data with `from_state`: one node per grid cell (or, once
+     * compacted or reordered, distinct cells within the grid), neighbour indices within `nodes`,
+     * finite graffiti and hyper parameters, a hop distance of at least 1 and a temperature
+     * above 0
+     */
+    pub fn validate(&self) -> Result<(), UniverseError> {
+        let grid_cells = (self.size * self.size) as usize;
+        if self.cell_nodes.is_none() && self.nodes.len() != grid_cells {
+            return Err(UniverseError::LengthMismatch {
+                expected: grid_cells,
+                found: self.nodes.len(),
+            });
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            let cell = node.cell;
+            let maps_back = (cell as usize) < grid_cells
+                && self.node_index(cell % self.size, cell / self.size) == Some(index);
+            if !maps_back {
+                return Err(UniverseError::InvalidCell { node: index, cell });
+            }
+        }
+
+        let hyper_params = &self.hyper_params;
+        let hyper_params_finite = [
+            hyper_params.gamma,
+            hyper_params.lambda,
+            hyper_params.beta,
+            hyper_params.persistence,
+            hyper_params.l0.unwrap_or(1.0),
+            hyper_params.red_mass,
+            hyper_params.blue_mass,
+            hyper_params.gamma2,
+            hyper_params.lambda2,
+            hyper_params.pheromone_weight,
+        ]
+        .iter()
+        .all(|value| value.is_finite());
+        if !hyper_params_finite {
+            return Err(UniverseError::NonFiniteHyperParams);
+        }
+        if hyper_params.hop_distance == 0 {
+            return Err(UniverseError::ZeroHopDistance);
+        }
+        if self.temperature.is_nan() || self.temperature <= 0.0 {
+            return Err(UniverseError::InvalidTemperature {
+                temperature: self.temperature,
+            });
+        }
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Some(neighbour) = node
+                .neighbours
+                .into_iter()
+                .find(|neighbour| *neighbour as usize >= self.nodes.len())
+            {
+                return Err(UniverseError::NeighbourOutOfRange {
+                    node: index,
+                    neighbour,
+                });
+            }
+            let graffiti = [node.graffiti, node.pheromone];
+            if !graffiti
+                .iter()
+                .all(|graffiti| graffiti.red.is_finite() && graffiti.blue.is_finite())
+            {
+                return Err(UniverseError::NonFiniteGraffiti { node: index });
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Deterministic 64-bit FNV-1a hash of the grid size, iteration and the agents, graffiti,
+     * pheromone and incoming agents of every node. Stable across platforms and runs, for pinning
+     * regression tests.
+     */
+    pub fn state_fingerprint(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let values = [self.size, self.iteration]
+            .into_iter()
+            .chain(self.nodes.iter().flat_map(|node| {
+                [
+                    node.index,
+                    node.red_agents,
+                    node.blue_agents,
+                    node.graffiti.red.to_bits(),
+                    node.graffiti.blue.to_bits(),
+                    node.pheromone.red.to_bits(),
+                    node.pheromone.blue.to_bits(),
+                ]
+                .into_iter()
+                .chain(node.agents_in.iter().flat_map(|agents_in| {
+                    [
+                        agents_in.top,
+                        agents_in.right,
+                        agents_in.bottom,
+                        agents_in.left,
+                    ]
+                }))
+            }));
+
+        values
+            .flat_map(u32::to_le_bytes)
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            })
+    }
+
+    /**
+     * Resume a universe paused with `into_state`. The movement rule, the display mode, the
+     * hyper parameter schedule, the periodic injections, the births and deaths and the recorders
+     * (occupancy window, agent tracking, visit and residence counts, deltas) are not part of the
+     * state; they are reset to the default and disabled. Hand-built or deserialized states are
+     * checked with `validate`, failing with the first inconsistency found.
+     */
+    pub fn from_state(state: UniverseState) -> Result<Universe2D, UniverseError> {
+        let universe = Universe2D::resume_state(state);
+        universe.validate()?;
+        Ok(universe)
+    }
+
+    /**
+     * `from_state` without the checks, for states taken from a universe
+     */
+    pub(super) fn resume_state(state: UniverseState) -> Universe2D {
+        let push_params = HyperParams {
+            beta: state.hyper_params.beta / state.temperature,
+            ..state.hyper_params
+        };
+        let nodes = state
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| {
+                let [top, right, bottom, left] = node.neighbours;
+                let agents_in = |[top, right, bottom, left]: [u32; 4]| {
+                    NeighbourAgentsOut2D::new(top, right, bottom, left)
+                };
+                let mut node_2d = Node2D {
+                    index: index as u32,
+                    cell: node.cell,
+                    neighbours: NeighbourData2D::new(top, right, bottom, left),
+                    graffiti: SpeciesGraffiti::new(node.red_graffiti, node.blue_graffiti),
+                    pheromone: SpeciesGraffiti::new(node.red_pheromone, node.blue_pheromone),
+                    push_strength: SpeciesPushStrength::new(0.0, 0.0),
+                    blue_agents: node.blue_agents,
+                    red_agents: node.red_agents,
+                    agents_out: [NeighbourAgentsOut2D::new(0, 0, 0, 0); 2],
+                    agents_in: [
+                        agents_in(node.red_agents_in),
+                        agents_in(node.blue_agents_in),
+                    ],
+                    active: node.active,
+                };
+                node_2d.update_push_strength(&push_params, state.size);
+                node_2d
+            })
+            .collect();
+
+        let mut universe = Universe2D {
+            size: state.size,
+            nodes,
+            iteration: state.iteration,
+            hyper_params: state.hyper_params,
+            cell_nodes: None,
+            movement_rule: Arc::new(PushWeighted),
+            occupancy: None,
+            trajectories: BTreeMap::new(),
+            visit_counts: None,
+            residence_counts: None,
+            delta_recording: None,
+            injections: Vec::new(),
+            birth_death: None,
+            display_mode: DisplayMode::default(),
+            temperature: state.temperature,
+            schedule: None,
+        };
+        universe.update_cell_nodes();
+        universe
+    }
+}
+
+#[cfg(test)]
+mod test_state {
+    use crate::{
+        hyper_params::HyperParams,
+        universe::{
+            universe_2d::test_support::{assert_golden, fingerprint_run},
+            Universe, Universe2D, UniverseError,
+        },
+    };
+
+    fn assert_same_nodes(a: &Universe2D, b: &Universe2D) {
+        assert_eq!(a.nodes.len(), b.nodes.len());
+        for (a, b) in a.nodes.iter().zip(b.nodes.iter()) {
+            assert_eq!(a.index, b.index);
+            assert_eq!((a.red_agents, a.blue_agents), (b.red_agents, b.blue_agents));
+            assert_eq!(a.graffiti.red, b.graffiti.red);
+            assert_eq!(a.graffiti.blue, b.graffiti.blue);
+        }
+    }
+
+    #[test]
+    fn test_state_round_trip_ticks_identically() {
+        let mut universe = Universe2D::new(8, 200);
+        universe.set_hyper_params(HyperParams::new(0.4, 0.3, 0.2));
+        universe.iterate(5);
+
+        let mut resumed = Universe2D::from_state(universe.clone().into_state()).unwrap();
+        assert_eq!(resumed.iteration, 5);
+        assert_same_nodes(&universe, &resumed);
+
+        universe.iterate(10);
+        resumed.iterate(10);
+        assert_same_nodes(&universe, &resumed);
+    }
+
+    #[test]
+    fn test_state_round_trip_keeps_pheromone() {
+        let mut universe = Universe2D::new(8, 200);
+        universe.set_hyper_params(HyperParams {
+            gamma2: 0.5,
+            lambda2: 0.3,
+            pheromone_weight: 1.0,
+            ..HyperParams::new(0.4, 0.3, 0.2)
+        });
+        universe.iterate(5);
+
+        let mut resumed = Universe2D::from_state(universe.clone().into_state()).unwrap();
+        for (a, b) in universe.nodes.iter().zip(resumed.nodes.iter()) {
+            assert_eq!(a.pheromone.red, b.pheromone.red);
+            assert_eq!(a.pheromone.blue, b.pheromone.blue);
+            assert_eq!(a.push_strength.red, b.push_strength.red);
+        }
+        assert!(resumed.nodes.iter().any(|node| node.pheromone.red > 0.0));
+
+        universe.iterate(10);
+        resumed.iterate(10);
+        assert_same_nodes(&universe, &resumed);
+    }
+
+    #[test]
+    fn test_state_round_trip_keeps_persistence() {
+        let mut universe = Universe2D::new(8, 300);
+        universe.set_hyper_params(HyperParams {
+            persistence: 2.0,
+            ..HyperParams::new(0.4, 0.3, 0.2)
+        });
+        universe.iterate(5);
+
+        let mut resumed = Universe2D::from_state(universe.clone().into_state()).unwrap();
+        universe.iterate(10);
+        resumed.iterate(10);
+        assert_same_nodes(&universe, &resumed);
+    }
+
+    #[test]
+    fn test_state_round_trip_keeps_temperature() {
+        let mut universe = Universe2D::new(8, 300);
+        universe.set_hyper_params(HyperParams::new(0.4, 0.3, 0.2));
+        universe.set_temperature(0.2).unwrap();
+        universe.iterate(5);
+
+        let mut resumed = Universe2D::from_state(universe.snapshot()).unwrap();
+        assert_eq!(resumed.temperature, 0.2);
+        universe.iterate(10);
+        resumed.iterate(10);
+        assert_eq!(resumed.state_fingerprint(), universe.state_fingerprint());
+    }
+
+    #[test]
+    fn test_state_round_trip_keeps_compacted_layout() {
+        let mut universe = Universe2D::new(6, 100);
+        let active: Vec<bool> = (0..36).map(|index| index / 6 != 2).collect();
+        universe.apply_mask(&active).unwrap();
+        universe.compact();
+
+        let mut resumed = Universe2D::from_state(universe.clone().into_state()).unwrap();
+        assert_eq!(resumed.agents_at(3, 3), universe.agents_at(3, 3));
+
+        universe.iterate(4);
+        resumed.iterate(4);
+        assert_same_nodes(&universe, &resumed);
+    }
+
+    #[test]
+    fn test_from_state_rejects_corrupted_state() {
+        let mut universe = Universe2D::new(5, 20);
+        universe.iterate(3);
+        assert_eq!(universe.validate(), Ok(()));
+
+        let mut state = universe.clone().into_state();
+        state.nodes[7].neighbours[2] = 25;
+        assert_eq!(
+            Universe2D::from_state(state).err(),
+            Some(UniverseError::NeighbourOutOfRange {
+                node: 7,
+                neighbour: 25
+            })
+        );
+
+        let mut state = universe.clone().into_state();
+        state.nodes[3].blue_graffiti = f32::NAN;
+        assert_eq!(
+            Universe2D::from_state(state).err(),
+            Some(UniverseError::NonFiniteGraffiti { node: 3 })
+        );
+
+        let mut state = universe.clone().into_state();
+        state.hyper_params.hop_distance = 0;
+        assert_eq!(
+            Universe2D::from_state(state).err(),
+            Some(UniverseError::ZeroHopDistance)
+        );
+
+        let mut state = universe.into_state();
+        state.nodes[24].cell = 30;
+        assert_eq!(
+            Universe2D::from_state(state).err(),
+            Some(UniverseError::InvalidCell { node: 24, cell: 30 })
+        );
+    }
+
+    #[test]
+    fn test_golden_fingerprint() {
+        let mut universe = Universe2D::new(4, 100);
+        universe.iterate(2);
+
+        assert_golden(
+            &universe,
+            &[
+                (5, 5),
+                (8, 2),
+                (4, 11),
+                (13, 7),
+                (8, 6),
+                (6, 5),
+                (5, 8),
+                (5, 7),
+                (5, 5),
+                (4, 6),
+                (10, 4),
+                (3, 2),
+                (9, 8),
+                (6, 10),
+                (5, 7),
+                (4, 7),
+            ],
+        );
+        assert_eq!(universe.state_fingerprint(), 0x5cf4_fb43_b454_89c2);
+
+        universe.tick();
+        assert_ne!(universe.state_fingerprint(), 0x5cf4_fb43_b454_89c2);
+    }
+
+    #[test]
+    fn test_fingerprint_covers_pheromone_and_agents_in() {
+        let mut universe = Universe2D::new(4, 20);
+        universe.tick();
+        let fingerprint = universe.state_fingerprint();
+
+        let mut pheromone = universe.clone();
+        pheromone.nodes[3].pheromone.blue += 1.0;
+        assert_ne!(pheromone.state_fingerprint(), fingerprint);
+
+        let mut agents_in = universe.clone();
+        agents_in.nodes[3].agents_in[1].left += 1;
+        assert_ne!(agents_in.state_fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn test_fingerprint_run_is_deterministic() {
+        for seed in [1, 2, 3] {
+            assert_eq!(
+                fingerprint_run(seed, 12, 500, 20),
+                fingerprint_run(seed, 12, 500, 20)
+            );
+        }
+        assert_ne!(
+            fingerprint_run(1, 12, 500, 20),
+            fingerprint_run(2, 12, 500, 20)
+        );
+    }
+
+    #[test]
+    fn test_verify_reproducible() {
+        assert!(Universe2D::verify_reproducible(
+            10,
+            400,
+            HyperParams::default(),
+            5,
+            15
+        ));
+        assert!(Universe2D::verify_reproducible(
+            6,
+            100,
+            HyperParams::new(0.5, 0.1, 0.5),
+            9,
+            30
+        ));
+    }
+}
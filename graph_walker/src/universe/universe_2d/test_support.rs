@@ -0,0 +1,36 @@
+use crate::{
+    hyper_params::HyperParams,
+    universe::{ComputationType, Universe2D},
+};
+
+/**
+ * Assert that the (red, blue) agent counts of the nodes, in node order, equal `expected`
+ */
+pub(crate) fn assert_golden(universe: &Universe2D, expected: &[(u32, u32)]) {
+    assert_eq!(universe.nodes.len(), expected.len(), "number of nodes");
+
+    for (node, (red, blue)) in universe.nodes.iter().zip(expected) {
+        assert_eq!(node.red_agents, *red, "red agents on index {}", node.index);
+        assert_eq!(
+            node.blue_agents, *blue,
+            "blue agents on index {}",
+            node.index
+        );
+    }
+}
+
+/**
+ * `state_fingerprint` after `iters` ticks of a `size * size` universe with `agents` agents of
+ * each species placed at random nodes drawn from `seed`, ticked like `iterate` with the default
+ * hyper parameters
+ */
+pub(crate) fn fingerprint_run(seed: u64, size: u32, agents: u32, iters: u32) -> u64 {
+    Universe2D::seeded_run_fingerprint(
+        size,
+        agents,
+        HyperParams::default(),
+        seed,
+        iters,
+        ComputationType::Parallel,
+    )
+}
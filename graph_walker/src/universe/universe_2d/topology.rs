@@ -0,0 +1,427 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    neighbour_data::{NeigbourIndeces2D, NeighbourData2D},
+    universe::{Universe2D, UniverseError},
+};
+
+impl Universe2D {
+    /**
+     * Turn every node whose entry in the row-major `active` mask is false into a wall: its agents
+     * and graffiti are removed and edges leading into it are bent back to the node they start
+     * from, so agents bounce off the wall instead of entering it
+     */
+    pub fn apply_mask(&mut self, active: &[bool]) -> Result<(), UniverseError> {
+        let expected = (self.size * self.size) as usize;
+        if active.len() != expected {
+            return Err(UniverseError::LengthMismatch {
+                expected,
+                found: active.len(),
+            });
+        }
+
+        for node in self.nodes.iter_mut() {
+            if !active[node.cell as usize] {
+                node.active = false;
+                node.red_agents = 0;
+                node.blue_agents = 0;
+                node.graffiti.red = 0.0;
+                node.graffiti.blue = 0.0;
+            }
+        }
+
+        let active: Vec<bool> = self.nodes.iter().map(|node| node.active).collect();
+        for node in self.nodes.iter_mut() {
+            let index = node.index;
+            let wall_to_self = |neighbour_idx: u32| {
+                if node.active && active[neighbour_idx as usize] {
+                    neighbour_idx
+                } else {
+                    index
+                }
+            };
+            let neighbours = node.neighbours;
+            node.neighbours = NeigbourIndeces2D::new(
+                wall_to_self(neighbours.top),
+                wall_to_self(neighbours.right),
+                wall_to_self(neighbours.bottom),
+                wall_to_self(neighbours.left),
+            );
+        }
+
+        Ok(())
+    }
+
+    /**
+     * The undirected edges (i, j), i < j, of the neighbour graph between node positions, sorted.
+     * Walls and the self-loops they leave behind are not included.
+     */
+    pub fn adjacency_edges(&self) -> Vec<(u32, u32)> {
+        let mut edges: Vec<(u32, u32)> = self
+            .nodes
+            .iter()
+            .filter(|node| node.active)
+            .flat_map(|node| {
+                node.neighbours
+                    .into_iter()
+                    .filter(move |neighbour_idx| *neighbour_idx != node.index)
+                    .map(move |neighbour_idx| {
+                        (node.index.min(neighbour_idx), node.index.max(neighbour_idx))
+                    })
+            })
+            .collect();
+
+        edges.sort_unstable();
+        edges.dedup();
+        edges
+    }
+
+    /**
+     * Dense symmetric 0/1 adjacency matrix of the neighbour graph, see `adjacency_edges`
+     */
+    pub fn adjacency_matrix(&self) -> Vec<Vec<u8>> {
+        let mut matrix = vec![vec![0; self.nodes.len()]; self.nodes.len()];
+        for (i, j) in self.adjacency_edges() {
+            matrix[i as usize][j as usize] = 1;
+            matrix[j as usize][i as usize] = 1;
+        }
+        matrix
+    }
+
+    /**
+     * For every node, its position in `nodes` after `compact`, or `u32::MAX` if it is masked out
+     */
+    pub fn compact_index_map(&self) -> Vec<u32> {
+        let mut next_index = 0;
+        self.nodes
+            .iter()
+            .map(|node| {
+                if node.active {
+                    next_index += 1;
+                    next_index - 1
+                } else {
+                    u32::MAX
+                }
+            })
+            .collect()
+    }
+
+    /**
+     * Drop all masked out nodes so that ticks no longer spend time on walls. Node indices and
+     * neighbour references are renumbered; grid coordinates are kept. Recorded occupancy and
+     * residence counts and unpopped deltas refer to the old numbering and are dropped; the visit
+     * counts are kept per cell.
+     */
+    pub fn compact(&mut self) {
+        let index_map = self.compact_index_map();
+
+        let nodes = core::mem::take(&mut self.nodes);
+        self.nodes = nodes
+            .into_iter()
+            .filter(|node| node.active)
+            .map(|mut node| {
+                node.index = index_map[node.index as usize];
+                node.neighbours = NeigbourIndeces2D::new(
+                    index_map[node.neighbours.top as usize],
+                    index_map[node.neighbours.right as usize],
+                    index_map[node.neighbours.bottom as usize],
+                    index_map[node.neighbours.left as usize],
+                );
+                node
+            })
+            .collect();
+        self.update_cell_nodes();
+        self.reset_occupancy_window();
+        if self.residence_counts.is_some() {
+            self.residence_counts = None;
+            self.enable_residence_tracking();
+        }
+        if self.delta_recording.is_some() {
+            self.start_delta_recording();
+        }
+    }
+
+    /**
+     * Exchange the places of the nodes `idx_a` and `idx_b` in `nodes`. Each node keeps its grid
+     * cell, state and neighbours; every neighbour reference, the cell lookup and the recorders
+     * are renumbered, so ticking gives the same result per cell as without the swap.
+     */
+    pub fn swap_nodes(&mut self, idx_a: u32, idx_b: u32) -> Result<(), UniverseError> {
+        for node in [idx_a, idx_b] {
+            if node as usize >= self.nodes.len() {
+                return Err(UniverseError::NodeOutOfRange { node });
+            }
+        }
+        if idx_a == idx_b {
+            return Ok(());
+        }
+
+        let renumber = |index: u32| match index {
+            _ if index == idx_a => idx_b,
+            _ if index == idx_b => idx_a,
+            _ => index,
+        };
+        self.nodes.swap(idx_a as usize, idx_b as usize);
+        self.nodes[idx_a as usize].index = idx_a;
+        self.nodes[idx_b as usize].index = idx_b;
+        for node in self.nodes.iter_mut() {
+            let neighbours = node.neighbours;
+            node.neighbours = NeigbourIndeces2D::new(
+                renumber(neighbours.top),
+                renumber(neighbours.right),
+                renumber(neighbours.bottom),
+                renumber(neighbours.left),
+            );
+        }
+        self.update_cell_nodes();
+
+        let (a, b) = (idx_a as usize, idx_b as usize);
+        if let Some(residence_counts) = &mut self.residence_counts {
+            residence_counts.swap(a, b);
+        }
+        self.swap_occupancy(a, b);
+        self.swap_delta_nodes(idx_a, idx_b);
+        Ok(())
+    }
+
+    /**
+     * Reorder the active nodes with `swap_nodes` so that their red agent counts increase along
+     * `nodes`, keeping the order of equal counts. Walls stay where they are.
+     */
+    pub fn sort_nodes_by_red_agents(&mut self) {
+        let active: Vec<u32> = (0..self.nodes.len() as u32)
+            .filter(|index| self.nodes[*index as usize].active)
+            .collect();
+        let mut order = active.clone();
+        order.sort_by_key(|index| self.nodes[*index as usize].red_agents);
+
+        // position of the state that started at each node, and the reverse
+        let mut position_of: Vec<u32> = (0..self.nodes.len() as u32).collect();
+        let mut origin_at = position_of.clone();
+        for (target, origin) in active.into_iter().zip(order) {
+            let current = position_of[origin as usize];
+            if current == target {
+                continue;
+            }
+
+            self.swap_nodes(target, current).unwrap();
+            let displaced = origin_at[target as usize];
+            position_of[displaced as usize] = current;
+            origin_at[current as usize] = displaced;
+            position_of[origin as usize] = target;
+            origin_at[target as usize] = origin;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_topology {
+    use crate::{
+        agent_species::{AgentId, AgentSpecies},
+        universe::{Universe, Universe2D, UniverseError},
+    };
+
+    fn total_agents(universe: &Universe2D) -> u32 {
+        let stats = universe.tick_stats();
+        stats.red_agents + stats.blue_agents
+    }
+
+    #[test]
+    fn test_mask_and_compact() {
+        let mut universe = Universe2D::new(10, 500);
+        // left half of the grid becomes a wall
+        let active: Vec<bool> = (0..100).map(|index| index % 10 >= 5).collect();
+
+        universe.apply_mask(&active).unwrap();
+        let agents = total_agents(&universe);
+        assert!(agents > 0 && agents < 1000);
+
+        let index_map = universe.compact_index_map();
+        assert_eq!(index_map[0], u32::MAX);
+        assert_eq!(index_map[5], 0);
+        assert_eq!(index_map[15], 5);
+
+        universe.compact();
+        assert_eq!(universe.nodes.len(), 50);
+        for (index, node) in universe.nodes.iter().enumerate() {
+            assert_eq!(node.index, index as u32);
+            assert!(node
+                .neighbours
+                .into_iter()
+                .all(|neighbour_idx| (neighbour_idx as usize) < 50));
+        }
+        assert_eq!(universe.agents_at(0, 0), (0, 0));
+        assert_eq!(
+            universe.agents_at(5, 1),
+            (universe.nodes[5].red_agents, universe.nodes[5].blue_agents)
+        );
+
+        universe.iterate(5);
+        assert_eq!(total_agents(&universe), agents);
+    }
+
+    #[test]
+    fn test_apply_mask_length_mismatch() {
+        let mut universe = Universe2D::new(4, 10);
+
+        assert_eq!(
+            universe.apply_mask(&[true; 3]),
+            Err(UniverseError::LengthMismatch {
+                expected: 16,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_torus_adjacency() {
+        let universe = Universe2D::new(10, 0);
+
+        let edges = universe.adjacency_edges();
+        assert_eq!(edges.len(), 2 * 10 * 10);
+        assert!(edges.contains(&(0, 1)));
+        assert!(edges.contains(&(0, 9)));
+        assert!(edges.contains(&(0, 90)));
+
+        let matrix = universe.adjacency_matrix();
+        assert!(matrix.iter().all(|row| row.iter().sum::<u8>() == 4));
+        assert_eq!(matrix[0][90], matrix[90][0]);
+    }
+
+    #[test]
+    fn test_masked_adjacency() {
+        let mut universe = Universe2D::new(4, 0);
+        let mut active = [true; 16];
+        active[5] = false;
+        universe.apply_mask(&active).unwrap();
+
+        // the four edges of the masked node disappear
+        assert_eq!(universe.adjacency_edges().len(), 2 * 16 - 4);
+    }
+
+    #[test]
+    fn test_swap_nodes_preserves_neighbours() {
+        let mut universe = Universe2D::new(6, 200);
+        universe.iterate(3);
+        let neighbour_cells = |universe: &Universe2D| -> Vec<Vec<u32>> {
+            (0..36)
+                .map(|cell| {
+                    let node = &universe.nodes[universe.node_index(cell % 6, cell / 6).unwrap()];
+                    node.neighbours
+                        .into_iter()
+                        .map(|neighbour| universe.node_cell(neighbour as usize))
+                        .collect()
+                })
+                .collect()
+        };
+        let neighbours = neighbour_cells(&universe);
+        let (a, b) = (universe.nodes[2].clone(), universe.nodes[29].clone());
+
+        universe.swap_nodes(2, 29).unwrap();
+
+        assert_eq!(neighbour_cells(&universe), neighbours);
+        assert_eq!(universe.adjacency_edges().len(), 2 * 36);
+        assert_eq!((universe.nodes[2].index, universe.nodes[2].cell), (2, 29));
+        assert_eq!(universe.nodes[2].red_agents, b.red_agents);
+        assert_eq!(universe.nodes[29].blue_agents, a.blue_agents);
+        assert_eq!(universe.node_index(2, 0), Some(29));
+        assert_eq!(universe.agents_at(5, 4), (b.red_agents, b.blue_agents));
+        assert!(universe.validate().is_ok());
+
+        assert_eq!(
+            universe.swap_nodes(2, 36),
+            Err(UniverseError::NodeOutOfRange { node: 36 })
+        );
+    }
+
+    #[test]
+    fn test_swapped_universe_ticks_like_the_original() {
+        let mut universe = Universe2D::new(8, 300);
+        let mut active = vec![true; 64];
+        active[10] = false;
+        universe.apply_mask(&active).unwrap();
+        universe.tick();
+        universe.enable_visit_counting();
+        let mut swapped = universe.clone();
+
+        swapped.swap_nodes(3, 40).unwrap();
+        swapped.swap_nodes(10, 17).unwrap();
+        swapped.sort_nodes_by_red_agents();
+        universe.iterate(10);
+        swapped.iterate(10);
+
+        assert_eq!(
+            universe.visit_frequency(AgentSpecies::Red),
+            swapped.visit_frequency(AgentSpecies::Red)
+        );
+        for (y, x) in (0..8).flat_map(|y| (0..8).map(move |x| (y, x))) {
+            assert_eq!(universe.agents_at(x, y), swapped.agents_at(x, y));
+            let (index, swapped_index) = (
+                universe.node_index(x, y).unwrap(),
+                swapped.node_index(x, y).unwrap(),
+            );
+            assert_eq!(
+                universe.nodes[index].graffiti.red,
+                swapped.nodes[swapped_index].graffiti.red
+            );
+        }
+    }
+
+    #[test]
+    fn test_row_major_inputs_follow_reordered_nodes() {
+        let mut universe = Universe2D::new(6, 100);
+        let mut sorted = universe.clone();
+        sorted.sort_nodes_by_red_agents();
+
+        let red: Vec<u32> = (0..36).collect();
+        let mut active = vec![true; 36];
+        active[7] = false;
+        let agent_id = AgentId {
+            species: AgentSpecies::Red,
+            ordinal: 100,
+        };
+        for universe in [&mut universe, &mut sorted] {
+            universe.load_agents(&red, &[1; 36]).unwrap();
+            universe.apply_mask(&active).unwrap();
+            universe.track_agent(agent_id);
+        }
+
+        for (y, x) in (0..6).flat_map(|y| (0..6).map(move |x| (y, x))) {
+            assert_eq!(universe.agents_at(x, y), sorted.agents_at(x, y));
+        }
+        assert_eq!(sorted.agents_at(1, 1), (0, 0));
+        assert_eq!(
+            universe.agent_position(agent_id),
+            sorted.agent_position(agent_id)
+        );
+        assert_eq!(
+            universe.tag_agent(3, 4, AgentSpecies::Red),
+            sorted.tag_agent(3, 4, AgentSpecies::Red)
+        );
+    }
+
+    #[test]
+    fn test_sort_nodes_by_red_agents() {
+        let mut universe = Universe2D::new(8, 300);
+        let mut active = vec![true; 64];
+        active[10] = false;
+        universe.apply_mask(&active).unwrap();
+        let mut counts: Vec<u32> = universe.nodes.iter().map(|node| node.red_agents).collect();
+        let agents = total_agents(&universe);
+
+        universe.sort_nodes_by_red_agents();
+
+        let sorted: Vec<u32> = universe
+            .nodes
+            .iter()
+            .filter(|node| node.active)
+            .map(|node| node.red_agents)
+            .collect();
+        assert!(sorted.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(!universe.nodes[10].active);
+        assert_eq!(total_agents(&universe), agents);
+        counts.remove(10);
+        counts.sort();
+        assert_eq!(sorted, counts);
+    }
+}
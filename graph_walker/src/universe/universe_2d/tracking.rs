@@ -0,0 +1,190 @@
+use alloc::{vec, vec::Vec};
+use oorandom::Rand32;
+
+use crate::{
+    agent_species::{AgentId, AgentSpecies},
+    nodes::{Node, Node2D},
+    universe::Universe2D,
+};
+
+impl Universe2D {
+    /**
+     * Start recording the (x, y) positions of `agent_id` after every tick. Agents of a species
+     * are interchangeable, so every tick the tracked agent follows one of the moves made by the
+     * agents of its node, picked at random.
+     */
+    pub fn track_agent(&mut self, agent_id: AgentId) {
+        let mut remaining = agent_id.ordinal;
+        let index = (0..self.size * self.size)
+            .filter_map(|cell| self.node_index(cell % self.size, cell / self.size))
+            .find(|index| {
+                let agents = self.nodes[*index].get_agents_with_species(&agent_id.species);
+                if remaining < agents {
+                    return true;
+                }
+                remaining -= agents;
+                false
+            })
+            .unwrap_or_else(|| panic!("there is no agent {:?}", agent_id));
+
+        let cell = self.node_cell(index);
+        self.trajectories
+            .insert(agent_id, vec![(cell % self.size, cell / self.size)]);
+    }
+
+    /**
+     * Tag one of the `species` agents on the node at (x, y) and track it from now on.
+     * The returned id is unique among the tracked agents.
+     */
+    pub fn tag_agent(&mut self, x: u32, y: u32, species: AgentSpecies) -> AgentId {
+        let has_agent = self
+            .node_index(x, y)
+            .is_some_and(|index| self.nodes[index].get_agents_with_species(&species) > 0);
+        if !has_agent {
+            panic!("there is no {:?} agent at ({}, {})", species, x, y);
+        }
+
+        let agents_before: u32 = (0..y * self.size + x)
+            .filter_map(|cell| self.node_index(cell % self.size, cell / self.size))
+            .map(|index| self.nodes[index].get_agents_with_species(&species))
+            .sum();
+        let agent_id = (agents_before..)
+            .map(|ordinal| AgentId { species, ordinal })
+            .find(|agent_id| !self.trajectories.contains_key(agent_id))
+            .unwrap();
+
+        self.trajectories.insert(agent_id, vec![(x, y)]);
+        agent_id
+    }
+
+    /**
+     * The current position of a tracked agent
+     */
+    pub fn agent_position(&self, agent_id: AgentId) -> Option<(u32, u32)> {
+        self.trajectories.get(&agent_id)?.last().copied()
+    }
+
+    /**
+     * The positions `agent_id` occupied since tracking started, oldest first
+     */
+    pub fn agent_trajectory(&self, agent_id: AgentId) -> Option<&[(u32, u32)]> {
+        self.trajectories.get(&agent_id).map(Vec::as_slice)
+    }
+
+    /**
+     * Move every tracked agent along with the agents leaving its node. Hop 0 adds the position
+     * of the new tick, the further hops of `HyperParams::hop_distance` move it on from there.
+     */
+    pub(super) fn move_tracked_agents(&mut self, nodes_with_agents_out: &[Node2D], hop: u32) {
+        if self.trajectories.is_empty() {
+            return;
+        }
+
+        let mut trajectories = core::mem::take(&mut self.trajectories);
+        for (agent_id, trajectory) in trajectories.iter_mut() {
+            let (x, y) = *trajectory.last().unwrap();
+            let Some(index) = self.node_index(x, y) else {
+                continue;
+            };
+            let node = &nodes_with_agents_out[index];
+            let agents_out = match agent_id.species {
+                AgentSpecies::Red => node.agents_out[0],
+                AgentSpecies::Blue => node.agents_out[1],
+            };
+
+            let moved: u32 = agents_out.into_iter().sum();
+            let next_index = if moved == 0 {
+                index // the tracked agent was removed from its node, it stays put
+            } else {
+                let mut prng = Rand32::new(
+                    ((self.iteration as u64) << 32)
+                        ^ ((hop as u64) << 48)
+                        ^ agent_id.ordinal as u64
+                        ^ self.node_cell(index) as u64,
+                );
+                let mut pick = prng.rand_range(0..moved);
+                let direction = agents_out
+                    .into_iter()
+                    .position(|agents| {
+                        if pick < agents {
+                            return true;
+                        }
+                        pick -= agents;
+                        false
+                    })
+                    .unwrap();
+                node.neighbours.into_iter().nth(direction).unwrap() as usize
+            };
+
+            let cell = self.node_cell(next_index);
+            if hop > 0 {
+                trajectory.pop(); // one position per tick, the last hop replaces the earlier ones
+            }
+            trajectory.push((cell % self.size, cell / self.size));
+        }
+        self.trajectories = trajectories;
+    }
+}
+
+#[cfg(test)]
+mod test_tracking {
+    use crate::{
+        agent_species::{AgentId, AgentSpecies},
+        universe::{Universe, Universe2D},
+    };
+
+    #[test]
+    fn test_tracked_agent_moves_between_neighbours() {
+        let size = 6;
+        let mut universe = Universe2D::new(size, 100);
+        let agent_id = AgentId {
+            species: AgentSpecies::Red,
+            ordinal: 42,
+        };
+        assert_eq!(universe.agent_trajectory(agent_id), None);
+
+        universe.track_agent(agent_id);
+        universe.iterate(10);
+
+        let trajectory = universe.agent_trajectory(agent_id).unwrap();
+        assert_eq!(trajectory.len(), 11);
+        for step in trajectory.windows(2) {
+            let dx = step[0].0.abs_diff(step[1].0);
+            let dy = step[0].1.abs_diff(step[1].1);
+            let (dx, dy) = (dx.min(size - dx), dy.min(size - dy));
+            assert_eq!(dx + dy, 1, "invalid move {:?}", step);
+        }
+    }
+
+    #[test]
+    fn test_tag_agent() {
+        let mut universe = Universe2D::new(5, 0);
+        let mut blue = [0; 25];
+        blue[7] = 3;
+        universe.load_agents(&[1; 25], &blue).unwrap();
+
+        let first = universe.tag_agent(2, 1, AgentSpecies::Blue);
+        let second = universe.tag_agent(2, 1, AgentSpecies::Blue);
+        assert_ne!(first, second);
+        assert_eq!(universe.agent_position(first), Some((2, 1)));
+
+        let mut previous = (2, 1);
+        for _ in 0..6 {
+            universe.tick();
+            let position = universe.agent_position(first).unwrap();
+            let (dx, dy) = (
+                position.0.abs_diff(previous.0),
+                position.1.abs_diff(previous.1),
+            );
+            assert_eq!(dx.min(5 - dx) + dy.min(5 - dy), 1);
+            previous = position;
+        }
+        assert_eq!(universe.agent_trajectory(first).unwrap().len(), 7);
+
+        let untracked = AgentId {
+            species: AgentSpecies::Red,
+            ordinal: 0,
+        };
+        assert_eq!(universe.agent_position(untracked), None);
+    }
+}
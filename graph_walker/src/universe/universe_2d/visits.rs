@@ -0,0 +1,94 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{agent_species::AgentSpecies, nodes::Node, universe::Universe2D};
+
+impl Universe2D {
+    /**
+     * Start counting, per grid cell and species, the agent-ticks spent on it. Keeps existing
+     * counts.
+     */
+    pub fn enable_visit_counting(&mut self) {
+        if self.visit_counts.is_none() {
+            let cells = (self.size * self.size) as usize;
+            self.visit_counts = Some([vec![0; cells], vec![0; cells]]);
+        }
+    }
+
+    /**
+     * Set all visit counts back to 0, if counting is enabled
+     */
+    pub fn reset_visit_counts(&mut self) {
+        if let Some(visit_counts) = &mut self.visit_counts {
+            visit_counts.iter_mut().for_each(|counts| counts.fill(0));
+        }
+    }
+
+    /**
+     * Per grid cell, row-major (y * size + x), the number of agent-ticks `species` spent on it
+     * since counting was enabled or last reset; 0 for walls and removed cells. Empty if
+     * counting is disabled.
+     */
+    pub fn visit_frequency(&self, species: AgentSpecies) -> Vec<u64> {
+        match (&self.visit_counts, species) {
+            (None, _) => Vec::new(),
+            (Some([red, _]), AgentSpecies::Red) => red.clone(),
+            (Some([_, blue]), AgentSpecies::Blue) => blue.clone(),
+        }
+    }
+
+    /**
+     * Add the agents currently on every node to the visit counts, if counting is enabled
+     */
+    pub(super) fn count_visits(&mut self) {
+        let Some([red, blue]) = &mut self.visit_counts else {
+            return;
+        };
+
+        for node in &self.nodes {
+            red[node.cell as usize] += node.get_agents_with_species(&AgentSpecies::Red) as u64;
+            blue[node.cell as usize] += node.get_agents_with_species(&AgentSpecies::Blue) as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_visits {
+    use crate::{
+        agent_species::AgentSpecies,
+        universe::{Universe, Universe2D},
+    };
+
+    #[test]
+    fn test_visit_counts_sum_to_agent_ticks() {
+        let mut universe = Universe2D::new(5, 60);
+        assert!(universe.visit_frequency(AgentSpecies::Red).is_empty());
+
+        universe.enable_visit_counting();
+        universe.iterate(3);
+        universe.reset_visit_counts();
+        universe.iterate(7);
+
+        let red: u64 = universe.visit_frequency(AgentSpecies::Red).iter().sum();
+        let blue: u64 = universe.visit_frequency(AgentSpecies::Blue).iter().sum();
+        assert_eq!(red, 60 * 7);
+        assert_eq!(blue, 60 * 7);
+    }
+
+    #[test]
+    fn test_visit_frequency_by_cell() {
+        let mut universe = Universe2D::new(6, 60);
+        let mask: Vec<bool> = (0..36).map(|cell| cell != 8 && cell != 21).collect();
+        universe.apply_mask(&mask).unwrap();
+        universe.enable_visit_counting();
+        let mut compacted = universe.clone();
+        compacted.compact();
+        compacted.sort_nodes_by_red_agents();
+
+        universe.iterate(5);
+        compacted.iterate(5);
+        let visits = universe.visit_frequency(AgentSpecies::Blue);
+        assert_eq!(visits.len(), 36);
+        assert_eq!((visits[8], visits[21]), (0, 0));
+        assert_eq!(visits, compacted.visit_frequency(AgentSpecies::Blue));
+    }
+}
@@ -8,7 +8,7 @@ use crate::{
 use oorandom::Rand32;
 use pad::PadStr;
 use rayon::prelude::*;
-use std::{collections::HashMap, fmt};
+use std::fmt;
 
 pub struct Universe3D {
     size: u32,
@@ -18,16 +18,15 @@ pub struct Universe3D {
 }
 
 impl Universe for Universe3D {
-    fn new(size: u32, agent_size: u32) -> Universe3D {
-        let mut prng = Rand32::new(100);
+    fn new_seeded(size: u32, agent_size: u32, seed: u64) -> Universe3D {
+        let mut prng = Rand32::new(seed);
 
-        let mut edges: HashMap<u32, NeigbourIndeces3D> = HashMap::new(); // TODO: convert to array
+        // neighbours of node z * size^2 + y * size + x
+        let mut edges = Vec::with_capacity((size * size * size) as usize);
 
         for z in 0..size {
             for y in 0..size {
                 for x in 0..size {
-                    let index = z * (size * size) + y * size + x;
-
                     let top_index = ((z + size - 1) % size) * (size * size) + y * size + x;
                     let bottom_index = ((z + 1) % size) * (size * size) + y * size + x;
                     let front_index = z * (size * size) + ((y + size - 1) % size) * size + x;
@@ -44,7 +43,7 @@ impl Universe for Universe3D {
                         back_index,
                     );
 
-                    edges.insert(index, new_edges);
+                    edges.push(new_edges);
                 }
             }
         }
@@ -107,13 +106,13 @@ impl Universe for Universe3D {
 
 impl fmt::Debug for Universe3D {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} UNIVERSE 3D {}\n", "=".repeat(10), "=".repeat(10))?;
+        writeln!(f, "{} UNIVERSE 3D {}", "=".repeat(10), "=".repeat(10))?;
 
-        write!(f, "size: {}\n", self.size)?;
-        write!(f, "node size: {}\n", self.nodes.len())?;
-        write!(f, "iterations: {}\n", self.iteration)?;
+        writeln!(f, "size: {}", self.size)?;
+        writeln!(f, "node size: {}", self.nodes.len())?;
+        writeln!(f, "iterations: {}", self.iteration)?;
 
-        write!(f, "{}\n", "=".repeat(30))?;
+        writeln!(f, "{}", "=".repeat(30))?;
         for z in 0..self.size {
             for y in 0..self.size {
                 for x in 0..self.size {
@@ -138,7 +137,7 @@ impl fmt::Debug for Universe3D {
                         red_graffiti.to_string().with_exact_width(4)
                     )?;
                 }
-                write!(f, "|\n")?;
+                writeln!(f, "|")?;
             }
         }
         write!(f, "")
@@ -147,14 +146,14 @@ impl fmt::Debug for Universe3D {
 
 impl fmt::Display for Universe3D {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} UNIVERSE 3D {}\n", "=".repeat(10), "=".repeat(10))?;
+        writeln!(f, "{} UNIVERSE 3D {}", "=".repeat(10), "=".repeat(10))?;
 
-        write!(f, "size: {}\n", self.size)?;
-        write!(f, "node size: {}\n", self.nodes.len())?;
-        write!(f, "iterations: {}\n", self.iteration)?;
+        writeln!(f, "size: {}", self.size)?;
+        writeln!(f, "node size: {}", self.nodes.len())?;
+        writeln!(f, "iterations: {}", self.iteration)?;
 
         for z in 0..self.size {
-            write!(f, "z: {}\n", z)?;
+            writeln!(f, "z: {}", z)?;
             for y in 0..self.size {
                 for x in 0..self.size {
                     let index = z * (self.size * self.size) + y * self.size + x;
@@ -173,9 +172,9 @@ impl fmt::Display for Universe3D {
                         write!(f, "🟥")?;
                     }
                 }
-                write!(f, "\n")?;
+                writeln!(f)?;
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
 
         write!(f, "")
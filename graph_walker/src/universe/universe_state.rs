@@ -0,0 +1,43 @@
+use alloc::vec::Vec;
+
+use crate::hyper_params::HyperParams;
+
+/**
+ * Plain data of a single node within a `UniverseState`
+ */
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeState {
+    pub cell: u32,            // grid cell y * size + x of the node
+    pub neighbours: [u32; 4], // top, right, bottom, left
+    pub red_agents: u32,
+    pub blue_agents: u32,
+    pub red_graffiti: f32,
+    pub blue_graffiti: f32,
+    pub red_pheromone: f32,
+    pub blue_pheromone: f32,
+    pub red_agents_in: [u32; 4], // last tick's arrivals by direction of travel, like neighbours
+    pub blue_agents_in: [u32; 4],
+    pub active: bool,
+}
+
+/**
+ * Everything needed to pause a universe and resume it later as plain data.
+ * Push strengths are left out as they follow from the graffiti.
+ */
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UniverseState {
+    pub size: u32,
+    pub iteration: u32,
+    pub hyper_params: HyperParams,
+    pub temperature: f32, // see `Universe2D::set_temperature`
+    pub nodes: Vec<NodeState>,
+}
+
+/**
+ * The nodes changed in a single tick, as (node index, red agents delta, blue agents delta,
+ * red graffiti delta, blue graffiti delta, red pheromone delta, blue pheromone delta). Agent
+ * deltas wrap around, so apply them with `wrapping_add`.
+ */
+pub type CompressedDelta = Vec<(u32, u32, u32, f32, f32, f32, f32)>;